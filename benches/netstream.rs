@@ -0,0 +1,134 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::collections::VecDeque;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use netstream::{
+    netframe::{
+        consts::{NETFRAME_HEADER_SIZE_BYTES, NETFRAME_MAX_DATA_SIZE},
+        types::NetFrame,
+    },
+    netstream::{
+        consts::NETSTREAM_INTERNAL_CAPACITY,
+        types::{FramingStream, NetStream},
+    },
+};
+
+
+// copy based decoder NetStream used before moving to BytesMut,
+// kept here as the baseline. Buffers the same way it did, decoded
+// frames are drained off the front of a preallocated Vec.
+struct VecStream {
+    frames: VecDeque<(u8, Vec<u8>)>,
+    buffer: Vec<u8>,
+}
+
+impl Default for VecStream {
+    fn default() -> Self {
+        Self {
+            frames: Default::default(),
+            buffer: Vec::with_capacity(NETSTREAM_INTERNAL_CAPACITY),
+        }
+    }
+}
+
+impl VecStream {
+    fn write(
+        &mut self,
+        data: &[u8],
+    ) {
+        self.buffer.extend_from_slice(data);
+        let mut offset = 0;
+        while let Ok(metadata) = NetFrame::get_metadata(&self.buffer[offset..]) {
+            let end = offset + NETFRAME_HEADER_SIZE_BYTES + metadata.size as usize;
+            if self.buffer.len() < end {
+                break;
+            }
+            self.frames.push_back((
+                metadata.tag,
+                self.buffer[offset + NETFRAME_HEADER_SIZE_BYTES..end].to_vec(),
+            ));
+            offset = end;
+        }
+        self.buffer.drain(..offset);
+    }
+
+    fn next(&mut self) -> Option<(u8, Vec<u8>)> {
+        self.frames.pop_front()
+    }
+}
+
+fn input(
+    frame_size: usize,
+    frame_count: usize,
+) -> Vec<u8> {
+    NetFrame::new(0x01, vec![0xAB; frame_size])
+        .encode()
+        .unwrap()
+        .repeat(frame_count)
+}
+
+fn decode(c: &mut Criterion) {
+    // (frame size, frames per run, read size)
+    let cases = [
+        (NETFRAME_MAX_DATA_SIZE, 16, 4096),
+        (NETFRAME_MAX_DATA_SIZE, 16, 16384),
+        (64, 1024, 4096),
+    ];
+
+    let mut group = c.benchmark_group("decode");
+    for (frame_size, frame_count, read_size) in cases {
+        let data = input(frame_size, frame_count);
+        let id = format!("{}B frames/{}B reads", frame_size, read_size);
+        group.throughput(Throughput::Bytes(data.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("netstream", &id), &data, |b, data| {
+            b.iter(|| {
+                let mut stream = NetStream::new();
+                let mut frames = 0;
+                for chunk in data.chunks(read_size) {
+                    let mut written = 0;
+                    while written < chunk.len() {
+                        written += stream.write(&chunk[written..]).unwrap();
+                        while stream.next().is_ok() {
+                            frames += 1;
+                        }
+                    }
+                }
+                assert_eq!(frames, frame_count);
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("vec_copy", &id), &data, |b, data| {
+            b.iter(|| {
+                let mut stream = VecStream::default();
+                let mut frames = 0;
+                for chunk in data.chunks(read_size) {
+                    stream.write(chunk);
+                    while stream.next().is_some() {
+                        frames += 1;
+                    }
+                }
+                assert_eq!(frames, frame_count);
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
@@ -0,0 +1,22 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+pub const LOGGER_DIRECTORY: &str = "logs";
+pub const LOGGER_FILE_NAME: &str = "netstream.log";
+// size rotation threshold
+pub const LOGGER_MAX_FILE_SIZE: u64 = 10 << 20;
+// rotated files kept next to the current one
+pub const LOGGER_MAX_FILES: usize = 7;
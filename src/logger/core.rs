@@ -0,0 +1,254 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use tracing_appender::{non_blocking, non_blocking::NonBlocking, rolling};
+use tracing_subscriber::{
+    fmt::Layer as FmtLayer,
+    layer::{Layered, SubscriberExt},
+    reload,
+    EnvFilter,
+    Layer,
+    Registry,
+};
+
+use super::{
+    consts::{LOGGER_DIRECTORY, LOGGER_FILE_NAME, LOGGER_MAX_FILES, LOGGER_MAX_FILE_SIZE},
+    error::LogError,
+    types::{BuildInfo, LogConfig, LogFormat, LogGuard, LogRotation, LogSizeWriter, LogTarget},
+};
+
+// filter of the installed subscriber, lets the level change at runtime
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+// what the output layers are stacked on
+type Filtered = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            target: LogTarget::Stdout,
+            format: LogFormat::Plain,
+            ansi: true,
+            directory: PathBuf::from(LOGGER_DIRECTORY),
+            file_name: LOGGER_FILE_NAME.to_string(),
+            rotation: LogRotation::Daily,
+            max_file_size: LOGGER_MAX_FILE_SIZE,
+            max_files: LOGGER_MAX_FILES,
+        }
+    }
+}
+
+impl BuildInfo {
+    pub const fn current() -> Self {
+        Self {
+            build_timestamp: env!("VERGEN_BUILD_TIMESTAMP"),
+            cargo_debug: env!("VERGEN_CARGO_DEBUG"),
+            cargo_features: env!("VERGEN_CARGO_FEATURES"),
+            cargo_opt_level: env!("VERGEN_CARGO_OPT_LEVEL"),
+            cargo_target_triple: env!("VERGEN_CARGO_TARGET_TRIPLE"),
+            git_branch: env!("VERGEN_GIT_BRANCH"),
+            git_commit_count: env!("VERGEN_GIT_COMMIT_COUNT"),
+            git_commit_timestamp: env!("VERGEN_GIT_COMMIT_TIMESTAMP"),
+            git_describe: env!("VERGEN_GIT_DESCRIBE"),
+            git_sha: env!("VERGEN_GIT_SHA"),
+            rustc_channel: env!("VERGEN_RUSTC_CHANNEL"),
+            rustc_commit_date: env!("VERGEN_RUSTC_COMMIT_DATE"),
+            rustc_commit_hash: env!("VERGEN_RUSTC_COMMIT_HASH"),
+            rustc_host_triple: env!("VERGEN_RUSTC_HOST_TRIPLE"),
+            rustc_llvm_version: env!("VERGEN_RUSTC_LLVM_VERSION"),
+            rustc_semver: env!("VERGEN_RUSTC_SEMVER"),
+            sysinfo_name: env!("VERGEN_SYSINFO_NAME"),
+            sysinfo_os_version: env!("VERGEN_SYSINFO_OS_VERSION"),
+            sysinfo_total_memory: env!("VERGEN_SYSINFO_TOTAL_MEMORY"),
+            sysinfo_cpu_vendor: env!("VERGEN_SYSINFO_CPU_VENDOR"),
+            sysinfo_cpu_brand: env!("VERGEN_SYSINFO_CPU_BRAND"),
+        }
+    }
+}
+
+// one line summary, the rest is in the Debug/Serialize output
+impl fmt::Display for BuildInfo {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}, {}) built {} by rustc {} for {}",
+            self.git_describe,
+            self.git_sha,
+            self.git_branch,
+            self.build_timestamp,
+            self.rustc_semver,
+            self.cargo_target_triple
+        )
+    }
+}
+
+// replaces the filter of the subscriber installed by try_init(),
+// directives use the RUST_LOG syntax, i.e. "info" or "netstream=debug"
+pub fn set_level(directives: &str) -> Result<(), LogError> {
+    let filter = EnvFilter::try_new(directives)?;
+    match FILTER.get() {
+        Some(handle) => Ok(handle.reload(filter)?),
+        None => Err(LogError::NotInitialized),
+    }
+}
+
+// installs the global subscriber, nothing in the library calls it, the
+// application opts in. Fails instead of replacing one that is already
+// there. Lines are written by background threads that live as long as
+// the returned guard.
+pub fn try_init(config: &LogConfig) -> Result<LogGuard, LogError> {
+    let (filter, handle) = reload::Layer::new(EnvFilter::from_default_env());
+    let mut layers = Vec::new();
+    let mut guards = Vec::new();
+    if config.target != LogTarget::File {
+        let (writer, guard) = non_blocking(io::stdout());
+        layers.push(output_layer(writer, config.format, config.ansi));
+        guards.push(guard);
+    }
+    if config.target != LogTarget::Stdout {
+        let (writer, guard) = file_writer(config)?;
+        layers.push(output_layer(writer, config.format, false));
+        guards.push(guard);
+    }
+    let subscriber = tracing_subscriber::registry().with(filter).with(layers);
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|_| LogError::AlreadyInitialized)?;
+    let _ = FILTER.set(handle);
+    Ok(LogGuard {
+        guards,
+    })
+}
+
+fn file_writer(config: &LogConfig) -> Result<(NonBlocking, non_blocking::WorkerGuard), LogError> {
+    let rotation = match config.rotation {
+        LogRotation::Size => {
+            let path = config.directory.join(&config.file_name);
+            let writer = LogSizeWriter::open(&path, config.max_file_size, config.max_files)?;
+            return Ok(non_blocking(writer));
+        }
+        LogRotation::Never => rolling::Rotation::NEVER,
+        LogRotation::Hourly => rolling::Rotation::HOURLY,
+        LogRotation::Daily => rolling::Rotation::DAILY,
+    };
+    let mut builder = rolling::Builder::new()
+        .rotation(rotation)
+        .filename_prefix(&config.file_name);
+    if config.max_files > 0 {
+        builder = builder.max_log_files(config.max_files);
+    }
+    Ok(non_blocking(builder.build(&config.directory)?))
+}
+
+fn output_layer(
+    writer: NonBlocking,
+    format: LogFormat,
+    ansi: bool,
+) -> Box<dyn Layer<Filtered> + Send + Sync> {
+    let layer = FmtLayer::default()
+        .with_writer(writer)
+        .with_target(true)
+        .with_thread_names(true)
+        .with_line_number(true)
+        .with_file(true)
+        .with_thread_ids(true);
+    match format {
+        LogFormat::Plain => layer.with_ansi(ansi).boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
+
+impl LogSizeWriter {
+    pub fn open(
+        path: &Path,
+        max_size: u64,
+        max_files: usize,
+    ) -> io::Result<Self> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            size: file.metadata()?.len(),
+            file,
+            max_size,
+            max_files,
+        })
+    }
+
+    // path of the index-th rotated file, 1 is the most recent one
+    pub fn rotated(
+        &self,
+        index: usize,
+    ) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        // with a limit the oldest file is overwritten by the one before it
+        let keep = match self.max_files {
+            0 => {
+                (1..)
+                    .find(|index| !self.rotated(*index).exists())
+                    .unwrap_or(1)
+            }
+            keep => keep,
+        };
+        for index in (1..keep).rev() {
+            let from = self.rotated(index);
+            if from.exists() {
+                fs::rename(from, self.rotated(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))?;
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for LogSizeWriter {
+    fn write(
+        &mut self,
+        buf: &[u8],
+    ) -> io::Result<usize> {
+        // a line bigger than max_size still goes into a file of its own
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
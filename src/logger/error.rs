@@ -0,0 +1,39 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use thiserror::Error;
+
+
+#[derive(Error, Debug)]
+pub enum LogError {
+    #[error("a global subscriber is already installed")]
+    AlreadyInitialized,
+
+    #[error("logger is not initialized")]
+    NotInitialized,
+
+    #[error("invalid filter: {0}")]
+    Filter(#[from] tracing_subscriber::filter::ParseError),
+
+    #[error("can not reload the filter: {0}")]
+    Reload(#[from] tracing_subscriber::reload::Error),
+
+    #[error("can not open the log file: {0}")]
+    Appender(#[from] tracing_appender::rolling::InitError),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,149 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::{fs, io::Write, path::PathBuf};
+
+use crate::logger::{
+    consts::{LOGGER_DIRECTORY, LOGGER_FILE_NAME, LOGGER_MAX_FILES, LOGGER_MAX_FILE_SIZE},
+    core::{set_level, try_init},
+    error::LogError,
+    types::{BuildInfo, LogConfig, LogFormat, LogRotation, LogSizeWriter, LogTarget},
+};
+
+
+// fresh directory in the temp directory, unique per test
+fn log_directory(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("netstream-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&path);
+    path
+}
+
+#[test]
+fn logger_ok_config() {
+    let config = LogConfig::default();
+    assert_eq!(config.target, LogTarget::Stdout);
+    assert_eq!(config.format, LogFormat::Plain);
+    assert!(config.ansi);
+    assert_eq!(config.directory, PathBuf::from(LOGGER_DIRECTORY));
+    assert_eq!(config.file_name, LOGGER_FILE_NAME);
+    assert_eq!(config.rotation, LogRotation::Daily);
+    assert_eq!(config.max_file_size, LOGGER_MAX_FILE_SIZE);
+    assert_eq!(config.max_files, LOGGER_MAX_FILES);
+    let parsed: LogConfig = toml::from_str(
+        r#"
+            target = "both"
+            format = "json"
+            rotation = "size"
+            max_file_size = 1024
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        parsed,
+        LogConfig {
+            target: LogTarget::Both,
+            format: LogFormat::Json,
+            rotation: LogRotation::Size,
+            max_file_size: 1024,
+            ..Default::default()
+        }
+    );
+    assert!(toml::from_str::<LogConfig>("rotation = \"weekly\"").is_err());
+}
+
+#[test]
+fn logger_ok_size_rotation() {
+    let directory = log_directory("size-rotation");
+    let path = directory.join("test.log");
+    let mut writer = LogSizeWriter::open(&path, 10, 2).unwrap();
+    // second line does not fit, third one goes over the limit alone
+    writer.write_all(b"line 1\n").unwrap();
+    writer.write_all(b"line 2\n").unwrap();
+    writer.write_all(b"a line over 10 bytes\n").unwrap();
+    writer.write_all(b"line 4\n").unwrap();
+    writer.flush().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "line 4\n");
+    assert_eq!(
+        fs::read_to_string(writer.rotated(1)).unwrap(),
+        "a line over 10 bytes\n"
+    );
+    assert_eq!(fs::read_to_string(writer.rotated(2)).unwrap(), "line 2\n");
+    // the oldest one fell off
+    assert!(!writer.rotated(3).exists());
+    // reopening picks up the size of the existing file
+    drop(writer);
+    let mut writer = LogSizeWriter::open(&path, 10, 2).unwrap();
+    assert_eq!(writer.size, 7);
+    writer.write_all(b"line 5\n").unwrap();
+    assert_eq!(fs::read_to_string(writer.rotated(1)).unwrap(), "line 4\n");
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn logger_ok_size_rotation_unlimited() {
+    let directory = log_directory("size-rotation-unlimited");
+    let path = directory.join("test.log");
+    let mut writer = LogSizeWriter::open(&path, 4, 0).unwrap();
+    for line in ["aaa\n", "bbb\n", "ccc\n", "ddd\n"] {
+        writer.write_all(line.as_bytes()).unwrap();
+    }
+    writer.flush().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "ddd\n");
+    assert_eq!(fs::read_to_string(writer.rotated(1)).unwrap(), "ccc\n");
+    assert_eq!(fs::read_to_string(writer.rotated(2)).unwrap(), "bbb\n");
+    assert_eq!(fs::read_to_string(writer.rotated(3)).unwrap(), "aaa\n");
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn logger_ok_build_info() {
+    let info = BuildInfo::current();
+    assert!(!info.git_sha.is_empty());
+    assert!(!info.rustc_semver.is_empty());
+    assert!(info.to_string().contains(info.git_sha));
+    let json = serde_json::to_value(&info).unwrap();
+    assert_eq!(json["cargo_target_triple"], info.cargo_target_triple);
+    assert_eq!(json.as_object().unwrap().len(), 21);
+}
+
+// the only test installing the global subscriber, nothing else in the
+// library does, so the first call succeeds
+#[test]
+fn logger_ok_try_init() {
+    assert!(matches!(set_level("info"), Err(LogError::NotInitialized)));
+    let directory = log_directory("try-init");
+    let config = LogConfig {
+        target: LogTarget::File,
+        directory: directory.clone(),
+        rotation: LogRotation::Never,
+        ..Default::default()
+    };
+    let guard = try_init(&config).unwrap();
+    assert!(matches!(
+        try_init(&config),
+        Err(LogError::AlreadyInitialized)
+    ));
+    assert!(matches!(
+        set_level("not a =filter"),
+        Err(LogError::Filter(_))
+    ));
+    set_level("netstream=info").unwrap();
+    tracing::info!("written to the file");
+    drop(guard);
+    let text = fs::read_to_string(directory.join(LOGGER_FILE_NAME)).unwrap();
+    assert!(text.contains("written to the file"));
+    fs::remove_dir_all(&directory).unwrap();
+}
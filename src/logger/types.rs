@@ -0,0 +1,120 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::{fs::File, path::PathBuf};
+
+use serde_derive::{Deserialize, Serialize};
+use tracing_appender::non_blocking::WorkerGuard;
+
+
+// output format of the installed subscriber
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    // human readable lines
+    #[default]
+    Plain,
+
+    // one json object per event
+    Json,
+}
+
+// where the log lines go
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogTarget {
+    #[default]
+    Stdout,
+    File,
+    Both,
+}
+
+// when the log file is replaced with a new one
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRotation {
+    Never,
+    Hourly,
+    #[default]
+    Daily,
+    // once the file would grow past max_file_size
+    Size,
+}
+
+// what try_init() sets up, also the [log] section of the
+// configuration file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub target: LogTarget,
+    pub format: LogFormat,
+    // colored stdout, files never get escape codes
+    pub ansi: bool,
+    pub directory: PathBuf,
+    // time based rotation appends the date to it
+    pub file_name: String,
+    pub rotation: LogRotation,
+    // bytes per file, size rotation only
+    pub max_file_size: u64,
+    // rotated files kept around, 0 keeps all of them
+    pub max_files: usize,
+}
+
+// what the binary was built from, collected by vergen in build.rs,
+// log it or hand it out as is, it serializes to a flat object
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuildInfo {
+    pub build_timestamp: &'static str,
+    pub cargo_debug: &'static str,
+    pub cargo_features: &'static str,
+    pub cargo_opt_level: &'static str,
+    pub cargo_target_triple: &'static str,
+    pub git_branch: &'static str,
+    pub git_commit_count: &'static str,
+    pub git_commit_timestamp: &'static str,
+    pub git_describe: &'static str,
+    pub git_sha: &'static str,
+    pub rustc_channel: &'static str,
+    pub rustc_commit_date: &'static str,
+    pub rustc_commit_hash: &'static str,
+    pub rustc_host_triple: &'static str,
+    pub rustc_llvm_version: &'static str,
+    pub rustc_semver: &'static str,
+    pub sysinfo_name: &'static str,
+    pub sysinfo_os_version: &'static str,
+    pub sysinfo_total_memory: &'static str,
+    pub sysinfo_cpu_vendor: &'static str,
+    pub sysinfo_cpu_brand: &'static str,
+}
+
+// keeps the background writers running, logs written before it is
+// dropped are flushed on drop, so hold on to it until the very end
+#[must_use]
+#[derive(Debug)]
+pub struct LogGuard {
+    pub guards: Vec<WorkerGuard>,
+}
+
+// appends to path, renaming it to path.1 (path.1 to path.2 and so on)
+// when a write would grow it past max_size
+#[derive(Debug)]
+pub struct LogSizeWriter {
+    pub path: PathBuf,
+    pub file: File,
+    pub size: u64,
+    pub max_size: u64,
+    pub max_files: usize,
+}
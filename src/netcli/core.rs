@@ -0,0 +1,398 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::{
+    fs::File,
+    io::{self, BufReader},
+};
+
+use bytes::Bytes;
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use serde::de::DeserializeOwned;
+use tokio::runtime::Handle;
+
+use super::types::{
+    NetCli,
+    NetCommand,
+    NetLimitArgs,
+    NetListenArgs,
+    NetLogArgs,
+    NetLogHandler,
+    NetPrintHandler,
+    NetReplArgs,
+    NetSendArgs,
+    NetServeArgs,
+};
+use crate::{
+    logger::types::LogConfig,
+    netclient::types::NetStreamClient,
+    netcodec::types::NetFrameCodec,
+    netconfig::{core::parse_name, types::NetConfig},
+    netfragment::types::NetFragmenter,
+    netframe::types::{NetFrame, NetFrameTag},
+    netrepl::{core::repl as net_repl, types::NetReplContext},
+    netserver::types::{ConnectionHandler, NetConnectionContext, NetServerConfig, NetStreamServer},
+    netsession::types::NetGoodbyeReason,
+    netstream::types::NetStreamConfig,
+};
+
+// defaults, then the configuration file, then NETSTREAM_* variables and
+// the log flags, also returns the variables apply_env() did not know.
+// The caller sets up the logger from it before calling run().
+pub fn load_config(cli: &NetCli) -> anyhow::Result<(NetConfig, Vec<String>)> {
+    let mut config = match &cli.config {
+        Some(path) => NetConfig::load(path)?,
+        None => NetConfig::default(),
+    };
+    let ignored = config.apply_env(std::env::vars())?;
+    cli.log.apply(&mut config.log);
+    Ok((config, ignored))
+}
+
+// every subcommand puts its flags on top and validates the result
+pub async fn run(
+    command: NetCommand,
+    config: NetConfig,
+) -> anyhow::Result<()> {
+    match command {
+        NetCommand::Serve(args) => serve(args, config).await,
+        NetCommand::Send(args) => send(args, config).await,
+        NetCommand::Listen(args) => listen(args, config).await,
+        NetCommand::Repl(args) => repl(args, config).await,
+    }
+}
+
+pub async fn serve(
+    args: NetServeArgs,
+    mut config: NetConfig,
+) -> anyhow::Result<()> {
+    args.apply(&mut config);
+    config.validate()?;
+    let handler = NetLogHandler {
+        echo: args.echo,
+    };
+    let server =
+        NetStreamServer::with_config(config.bind, config.server_config(), move || handler).await?;
+    run_server(server).await
+}
+
+pub async fn send(
+    args: NetSendArgs,
+    mut config: NetConfig,
+) -> anyhow::Result<()> {
+    args.limits.apply(&mut config.stream);
+    config.validate()?;
+    let address = args.connect.unwrap_or(config.bind);
+    let data = match &args.data {
+        Some(data) if args.hex => decode_hex(data)?,
+        Some(data) => data.as_bytes().to_vec(),
+        None => Vec::new(),
+    };
+    let mut client =
+        NetStreamClient::with_codec(address, NetFrameCodec::from(&config.stream)).await?;
+    if args.handshake {
+        client.handshake().await?;
+    }
+    let frame = NetFrame::with_kind(args.tag, data);
+    client.send_frame(frame).await?;
+    if args.handshake {
+        client.goodbye(NetGoodbyeReason::Normal).await?;
+    } else {
+        client.close().await?;
+    }
+    Ok(())
+}
+
+pub async fn listen(
+    args: NetListenArgs,
+    mut config: NetConfig,
+) -> anyhow::Result<()> {
+    if let Some(bind) = args.bind {
+        config.bind = bind;
+    }
+    args.limits.apply(&mut config.stream);
+    config.validate()?;
+    let handler = NetPrintHandler {
+        hex: args.hex,
+    };
+    // everything is printed, Control frames included
+    let server_config = NetServerConfig {
+        control: false,
+        ..config.server_config()
+    };
+    let server = NetStreamServer::with_config(config.bind, server_config, move || handler).await?;
+    run_server(server).await
+}
+
+// the repl blocks its thread on reading lines, so it gets one of its own
+pub async fn repl(
+    args: NetReplArgs,
+    mut config: NetConfig,
+) -> anyhow::Result<()> {
+    args.limits.apply(&mut config.stream);
+    config.validate()?;
+    let codec = NetFrameCodec::from(&config.stream);
+    let handle = Handle::current();
+    tokio::task::spawn_blocking(move || run_repl(handle, args, codec)).await?
+}
+
+fn run_repl(
+    handle: Handle,
+    args: NetReplArgs,
+    codec: NetFrameCodec,
+) -> anyhow::Result<()> {
+    let mut context = NetReplContext::new(handle, codec.clone());
+    if let Some(address) = args.connect {
+        eprintln!("{}", context.connect(address, codec, args.handshake)?);
+    }
+    let mut repl = net_repl(context);
+    match &args.script {
+        Some(path) if path.as_os_str() == "-" => repl.run_with_reader(io::stdin().lock())?,
+        Some(path) => repl.run_with_reader(BufReader::new(File::open(path)?))?,
+        None => repl.run()?,
+    }
+    let _ = repl.context_mut().disconnect();
+    Ok(())
+}
+
+// runs until ctrl-c, connected peers are told the server is shutting down
+async fn run_server<H: ConnectionHandler>(server: NetStreamServer<H>) -> anyhow::Result<()> {
+    eprintln!("Listening on {}", server.local_addr()?);
+    let shutdown = server.shutdown_handle();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            shutdown.shutdown();
+        }
+    });
+    server.run().await?;
+    Ok(())
+}
+
+impl NetLogArgs {
+    pub fn apply(
+        &self,
+        config: &mut LogConfig,
+    ) {
+        if let Some(format) = self.log_format {
+            config.format = format;
+        }
+        if let Some(target) = self.log_target {
+            config.target = target;
+        }
+        if let Some(directory) = &self.log_dir {
+            config.directory = directory.clone();
+        }
+        if let Some(rotation) = self.log_rotation {
+            config.rotation = rotation;
+        }
+        if self.no_ansi {
+            config.ansi = false;
+        }
+    }
+}
+
+impl NetLimitArgs {
+    pub fn apply(
+        &self,
+        config: &mut NetStreamConfig,
+    ) {
+        if let Some(max_frame_size) = self.max_frame_size {
+            config.max_frame_size = max_frame_size;
+        }
+        if let Some(max_queued_frames) = self.max_queued_frames {
+            config.max_queued_frames = max_queued_frames;
+        }
+        if let Some(max_buffered_bytes) = self.max_buffered_bytes {
+            config.max_buffered_bytes = max_buffered_bytes;
+        }
+        if let Some(checksum) = self.checksum {
+            config.checksum = checksum;
+        }
+        if let Some(delimiter) = self.delimiter {
+            config.delimiter = delimiter;
+        }
+        if let Some(mode) = self.mode {
+            config.mode = mode;
+        }
+    }
+}
+
+impl NetServeArgs {
+    pub fn apply(
+        &self,
+        config: &mut NetConfig,
+    ) {
+        if let Some(bind) = self.bind {
+            config.bind = bind;
+        }
+        self.limits.apply(&mut config.stream);
+        let config = &mut config.server;
+        if self.max_connections.is_some() {
+            config.max_connections = self.max_connections;
+        }
+        if let Some(max_outbound_bytes) = self.max_outbound_bytes {
+            config.max_outbound_bytes = max_outbound_bytes;
+        }
+        if let Some(outbound_policy) = self.outbound_policy {
+            config.outbound_policy = outbound_policy;
+        }
+        if let Some(read_chunk_size) = self.read_chunk_size {
+            config.read_chunk_size = read_chunk_size;
+        }
+        if self.require_handshake {
+            config.require_handshake = true;
+        }
+        if self.control {
+            config.control = true;
+        }
+    }
+}
+
+impl ConnectionHandler for NetLogHandler {
+    fn on_frame(
+        &mut self,
+        ctx: &mut NetConnectionContext,
+        tag: NetFrameTag,
+        data: Bytes,
+    ) {
+        tracing::info!(
+            "connection {}: {:?} frame, {} bytes",
+            ctx.id,
+            tag,
+            data.len()
+        );
+        if self.echo {
+            let _ = ctx.send(&NetFrame::with_kind(tag, data));
+        }
+    }
+
+    fn on_message(
+        &mut self,
+        ctx: &mut NetConnectionContext,
+        data: Bytes,
+    ) {
+        tracing::info!("connection {}: message, {} bytes", ctx.id, data.len());
+        if self.echo {
+            // echoed back in fragments, it may not fit a single frame
+            if let Ok(frames) = NetFragmenter::default().split(&data) {
+                for frame in frames {
+                    let _ = ctx.send(&frame);
+                }
+            }
+        }
+    }
+}
+
+impl ConnectionHandler for NetPrintHandler {
+    fn on_frame(
+        &mut self,
+        ctx: &mut NetConnectionContext,
+        tag: NetFrameTag,
+        data: Bytes,
+    ) {
+        println!(
+            "{} {:?} {} {}",
+            ctx.address,
+            tag,
+            data.len(),
+            format_payload(&data, self.hex)
+        );
+    }
+
+    fn on_message(
+        &mut self,
+        ctx: &mut NetConnectionContext,
+        data: Bytes,
+    ) {
+        println!(
+            "{} message {} {}",
+            ctx.address,
+            data.len(),
+            format_payload(&data, self.hex)
+        );
+    }
+}
+
+// clap parser for the enums of the library, they are named by serde.
+// Names are the ones of the configuration file, with '-' in place of '_'.
+pub fn enum_parser<T>(names: &'static [&'static str]) -> impl TypedValueParser<Value = T>
+where
+    T: DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    PossibleValuesParser::new(names.iter().copied()).try_map(|name| parse_name::<T>(&name))
+}
+
+// accepts tag names in kebab or snake case and numeric values,
+// decimal or 0x prefixed hex
+pub fn parse_tag(value: &str) -> Result<NetFrameTag, String> {
+    let tag = match value.to_ascii_lowercase().replace('_', "-").as_str() {
+        "generic" | "generic-message" => NetFrameTag::GenericMessage,
+        "single" | "single-message" => NetFrameTag::SingleMessage,
+        "multi" | "multi-message" => NetFrameTag::MultiMessage,
+        "control" => NetFrameTag::Control,
+        "hello" => NetFrameTag::Hello,
+        "goodbye" => NetFrameTag::Goodbye,
+        "ping" => NetFrameTag::Ping,
+        "pong" => NetFrameTag::Pong,
+        "reset" => NetFrameTag::Reset,
+        number => {
+            let byte = match number.strip_prefix("0x") {
+                Some(hex) => u8::from_str_radix(hex, 16),
+                None => number.parse::<u8>(),
+            };
+            NetFrameTag::from(byte.map_err(|_| format!("unknown tag: {}", value))?)
+        }
+    };
+    Ok(tag)
+}
+
+// whitespace between bytes is ignored, i.e. "de ad be ef"
+pub fn decode_hex(value: &str) -> anyhow::Result<Vec<u8>> {
+    let digits: Vec<u8> = value
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    let pairs = digits.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        anyhow::bail!("odd number of hex digits");
+    }
+    // from_str_radix() would take a sign too, e.g. "+f"
+    if let Some(byte) = digits.iter().find(|byte| !byte.is_ascii_hexdigit()) {
+        anyhow::bail!("invalid hex digit {:?}", char::from(*byte));
+    }
+    pairs
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair)?;
+            Ok(u8::from_str_radix(pair, 16)?)
+        })
+        .collect()
+}
+
+pub fn encode_hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// utf8 data without control characters is printed as is, anything else
+// as hex
+pub fn format_payload(
+    data: &[u8],
+    hex: bool,
+) -> String {
+    match std::str::from_utf8(data) {
+        Ok(text) if !hex && !text.chars().any(char::is_control) => text.to_string(),
+        _ => encode_hex(data),
+    }
+}
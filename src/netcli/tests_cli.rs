@@ -0,0 +1,225 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::time::Duration;
+
+use clap::Parser;
+
+use super::{
+    core::{decode_hex, encode_hex, format_payload, load_config, parse_tag, send},
+    types::{NetCli, NetCommand},
+};
+use crate::{
+    logger::types::{LogConfig, LogFormat, LogTarget},
+    netconfig::types::NetConfig,
+    netframe::types::{NetFrame, NetFrameChecksum, NetFrameTag},
+    netserver::{
+        consts::NETSERVER_DEFAULT_ADDRESS,
+        tests_netserver::Recorder,
+        types::{NetOutboundPolicy, NetServerConfig, NetStreamServer},
+    },
+};
+
+
+#[test]
+fn netcli_ok_serve_defaults() {
+    let cli = NetCli::try_parse_from(["netstream", "serve"]).unwrap();
+    assert_eq!(cli.log, Default::default());
+    assert_eq!(cli.config, None);
+    let NetCommand::Serve(args) = cli.command else {
+        panic!("not serve");
+    };
+    assert_eq!(args.bind, None);
+    let mut config = NetConfig::default();
+    args.apply(&mut config);
+    assert_eq!(config, NetConfig::default());
+    assert_eq!(config.bind, NETSERVER_DEFAULT_ADDRESS.parse().unwrap());
+    assert_eq!(config.server_config(), NetServerConfig::default());
+}
+
+#[test]
+fn netcli_ok_serve_flags() {
+    let cli = NetCli::try_parse_from([
+        "netstream",
+        "serve",
+        "--bind",
+        "0.0.0.0:7000",
+        "--max-connections",
+        "16",
+        "--max-frame-size",
+        "100000",
+        "--max-queued-frames",
+        "8",
+        "--checksum",
+        "xxhash32",
+        "--delimiter",
+        "126",
+        "--outbound-policy",
+        "drop-oldest",
+        "--require-handshake",
+        "--control",
+        "--log-format",
+        "json",
+        "--log-target",
+        "both",
+        "--log-dir",
+        "/tmp/netstream",
+        "--no-ansi",
+    ])
+    .unwrap();
+    let mut log = LogConfig::default();
+    cli.log.apply(&mut log);
+    assert_eq!(log.format, LogFormat::Json);
+    assert_eq!(log.target, LogTarget::Both);
+    assert_eq!(log.directory, std::path::Path::new("/tmp/netstream"));
+    assert!(!log.ansi);
+    let NetCommand::Serve(args) = cli.command else {
+        panic!("not serve");
+    };
+    let mut config = NetConfig::default();
+    args.apply(&mut config);
+    assert_eq!(config.bind, "0.0.0.0:7000".parse().unwrap());
+    let config = config.server_config();
+    assert_eq!(config.max_connections, Some(16));
+    assert_eq!(config.stream.max_frame_size, 100000);
+    assert_eq!(config.stream.max_queued_frames, 8);
+    assert_eq!(config.stream.checksum, NetFrameChecksum::XxHash32);
+    assert_eq!(config.stream.delimiter, 0x7E);
+    assert_eq!(config.outbound_policy, NetOutboundPolicy::DropOldest);
+    assert!(config.require_handshake);
+    assert!(config.control);
+    // untouched ones keep the defaults
+    let defaults = NetServerConfig::default();
+    assert_eq!(
+        config.stream.max_buffered_bytes,
+        defaults.stream.max_buffered_bytes
+    );
+    assert_eq!(config.max_outbound_bytes, defaults.max_outbound_bytes);
+}
+
+#[test]
+fn netcli_ok_flags_over_config() {
+    let mut config = NetConfig::default();
+    config.stream.checksum = NetFrameChecksum::Crc32;
+    config.server.max_connections = Some(4);
+    config.server.control = true;
+    let cli = NetCli::try_parse_from(["netstream", "serve", "--max-frame-size", "1000"]).unwrap();
+    let NetCommand::Serve(args) = cli.command else {
+        panic!("not serve");
+    };
+    args.apply(&mut config);
+    // flags win, the rest of the configuration stays as it was
+    assert_eq!(config.stream.max_frame_size, 1000);
+    assert_eq!(config.stream.checksum, NetFrameChecksum::Crc32);
+    assert_eq!(config.server.max_connections, Some(4));
+    assert!(config.server.control);
+}
+
+#[test]
+fn netcli_failure_arguments() {
+    assert!(NetCli::try_parse_from(["netstream"]).is_err());
+    assert!(NetCli::try_parse_from(["netstream", "serve", "--bind", "nope"]).is_err());
+    assert!(NetCli::try_parse_from(["netstream", "serve", "--checksum", "md5"]).is_err());
+    assert!(NetCli::try_parse_from(["netstream", "send", "--tag", "0x100"]).is_err());
+}
+
+#[test]
+fn netcli_ok_send_tag() {
+    let cli =
+        NetCli::try_parse_from(["netstream", "send", "--tag", "0x80", "--hex", "beef"]).unwrap();
+    let NetCommand::Send(args) = cli.command else {
+        panic!("not send");
+    };
+    assert_eq!(args.tag, NetFrameTag::from(0x80));
+    assert!(args.hex);
+    assert_eq!(args.data.as_deref(), Some("beef"));
+    assert_eq!(parse_tag("single-message"), Ok(NetFrameTag::SingleMessage));
+    assert_eq!(parse_tag("MULTI_MESSAGE"), Ok(NetFrameTag::MultiMessage));
+    assert_eq!(parse_tag("ping"), Ok(NetFrameTag::Ping));
+    assert_eq!(parse_tag("3"), Ok(NetFrameTag::Control));
+    assert_eq!(parse_tag("0x40"), Ok(NetFrameTag::from(0x40)));
+    assert!(parse_tag("nope").is_err());
+}
+
+#[test]
+fn netcli_ok_hex() {
+    assert_eq!(
+        decode_hex("de ad BE ef").unwrap(),
+        vec![0xDE, 0xAD, 0xBE, 0xEF]
+    );
+    assert_eq!(decode_hex("").unwrap(), Vec::<u8>::new());
+    assert!(decode_hex("abc").is_err());
+    assert!(decode_hex("zz").is_err());
+    assert!(decode_hex("+f").is_err());
+    assert!(decode_hex("-1").is_err());
+    assert_eq!(encode_hex(&[0x00, 0x7F, 0xFF]), "007fff");
+    assert_eq!(format_payload(b"hello", false), "hello");
+    assert_eq!(format_payload(b"hello", true), "68656c6c6f");
+    assert_eq!(format_payload(&[0x00, 0x01], false), "0001");
+}
+
+#[test]
+fn netcli_failure_config_file() {
+    let cli = NetCli::try_parse_from(["netstream", "--config", "missing.toml", "serve"]).unwrap();
+    let err = load_config(&cli).unwrap_err();
+    assert!(err.to_string().starts_with("can not read missing.toml"));
+}
+
+#[tokio::test]
+async fn netcli_ok_send() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let config = NetServerConfig {
+        require_handshake: true,
+        ..Default::default()
+    };
+    let server = NetStreamServer::with_config("127.0.0.1:0".parse().unwrap(), config, move || {
+        handler.clone()
+    })
+    .await
+    .unwrap();
+    let address = server.local_addr().unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    let NetCommand::Send(args) = NetCli::try_parse_from([
+        "netstream",
+        "send",
+        "--connect",
+        &address.to_string(),
+        "--tag",
+        "single",
+        "--handshake",
+        "hello",
+    ])
+    .unwrap()
+    .command
+    else {
+        panic!("not send");
+    };
+    send(args, NetConfig::default()).await.unwrap();
+    for _ in 0..100 {
+        if !recorder.frames().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(
+        recorder.frames(),
+        vec![NetFrame::with_kind(NetFrameTag::SingleMessage, "hello")]
+    );
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
@@ -0,0 +1,246 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::{net::SocketAddr, path::PathBuf};
+
+use clap::{Args, Parser, Subcommand};
+
+use super::core::{enum_parser, parse_tag};
+use crate::{
+    logger::types::{LogFormat, LogRotation, LogTarget},
+    netframe::types::{NetFrameChecksum, NetFrameTag},
+    netserver::types::NetOutboundPolicy,
+    netstream::types::NetStreamMode,
+};
+
+
+// command line of the netstream binary
+#[derive(Debug, Parser)]
+#[command(name = "netstream", version, about = "netframe server and tools")]
+pub struct NetCli {
+    #[command(flatten)]
+    pub log: NetLogArgs,
+
+    #[arg(
+        long,
+        global = true,
+        help = "configuration file, TOML or JSON (*.json), overridden by NETSTREAM_* and flags"
+    )]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: NetCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum NetCommand {
+    #[command(about = "run the server, frames are logged and optionally echoed back")]
+    Serve(NetServeArgs),
+
+    #[command(about = "connect to a server and send a single frame")]
+    Send(NetSendArgs),
+
+    #[command(about = "accept connections and print every frame received to stdout")]
+    Listen(NetListenArgs),
+
+    #[command(about = "interactive console, or a script of its commands")]
+    Repl(NetReplArgs),
+}
+
+// [log] section overrides, unset ones keep the configured values
+#[derive(Debug, Default, Clone, PartialEq, Eq, Args)]
+pub struct NetLogArgs {
+    #[arg(
+        long,
+        global = true,
+        value_parser = enum_parser::<LogFormat>(&["plain", "json"]),
+        help = "format of the log output"
+    )]
+    pub log_format: Option<LogFormat>,
+
+    #[arg(
+        long,
+        global = true,
+        value_parser = enum_parser::<LogTarget>(&["stdout", "file", "both"]),
+        help = "where the log lines go"
+    )]
+    pub log_target: Option<LogTarget>,
+
+    #[arg(long, global = true, help = "directory of the log files")]
+    pub log_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_parser = enum_parser::<LogRotation>(&["never", "hourly", "daily", "size"]),
+        help = "when the log file is replaced with a new one"
+    )]
+    pub log_rotation: Option<LogRotation>,
+
+    #[arg(long, global = true, help = "no colors in the stdout log")]
+    pub no_ansi: bool,
+}
+
+// stream limits shared by all subcommands, unset ones keep the defaults
+#[derive(Debug, Default, Clone, PartialEq, Eq, Args)]
+pub struct NetLimitArgs {
+    #[arg(long, help = "largest frame data accepted, in bytes")]
+    pub max_frame_size: Option<usize>,
+
+    #[arg(long, help = "decoded frames waiting to be handled")]
+    pub max_queued_frames: Option<usize>,
+
+    #[arg(long, help = "bytes held in the receive buffer")]
+    pub max_buffered_bytes: Option<usize>,
+
+    #[arg(
+        long,
+        value_parser = enum_parser::<NetFrameChecksum>(&["none", "crc32", "xxhash32"]),
+        help = "frame trailer, has to match the peer"
+    )]
+    pub checksum: Option<NetFrameChecksum>,
+
+    #[arg(long, help = "first byte of every frame, has to match the peer")]
+    pub delimiter: Option<u8>,
+
+    #[arg(
+        long,
+        value_parser = enum_parser::<NetStreamMode>(&["strict", "resync"]),
+        help = "on undecodable data fail the stream or skip ahead"
+    )]
+    pub mode: Option<NetStreamMode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct NetServeArgs {
+    #[arg(
+        short,
+        long,
+        help = "address to listen on, bind of the configuration if not given"
+    )]
+    pub bind: Option<SocketAddr>,
+
+    #[arg(long, help = "connections accepted at once, further ones are dropped")]
+    pub max_connections: Option<usize>,
+
+    #[command(flatten)]
+    pub limits: NetLimitArgs,
+
+    #[arg(
+        long,
+        help = "bytes queued for a peer before the outbound policy kicks in"
+    )]
+    pub max_outbound_bytes: Option<usize>,
+
+    #[arg(
+        long,
+        value_parser = enum_parser::<NetOutboundPolicy>(&["block", "drop-oldest", "disconnect"]),
+        help = "what to do with frames a slow peer does not read"
+    )]
+    pub outbound_policy: Option<NetOutboundPolicy>,
+
+    #[arg(long, help = "bytes read from a socket at once")]
+    pub read_chunk_size: Option<usize>,
+
+    #[arg(long, help = "reject peers that do not start with Hello")]
+    pub require_handshake: bool,
+
+    #[arg(
+        long,
+        help = "answer Control frames instead of passing them to the handler"
+    )]
+    pub control: bool,
+
+    #[arg(long, help = "send every received frame back to its peer")]
+    pub echo: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct NetSendArgs {
+    #[arg(
+        short,
+        long,
+        help = "address of the server, bind of the configuration if not given"
+    )]
+    pub connect: Option<SocketAddr>,
+
+    #[arg(
+        short,
+        long,
+        default_value = "generic-message",
+        value_parser = parse_tag,
+        help = "tag name (i.e. single-message) or value (i.e. 0x80)",
+    )]
+    pub tag: NetFrameTag,
+
+    #[arg(long, help = "data is hex encoded instead of utf8")]
+    pub hex: bool,
+
+    #[arg(
+        long,
+        help = "exchange Hello before sending and say Goodbye afterwards"
+    )]
+    pub handshake: bool,
+
+    #[command(flatten)]
+    pub limits: NetLimitArgs,
+
+    #[arg(help = "frame data, empty frame if omitted")]
+    pub data: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct NetListenArgs {
+    #[arg(
+        short,
+        long,
+        help = "address to listen on, bind of the configuration if not given"
+    )]
+    pub bind: Option<SocketAddr>,
+
+    #[arg(long, help = "print data as hex even if it is valid utf8")]
+    pub hex: bool,
+
+    #[command(flatten)]
+    pub limits: NetLimitArgs,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct NetReplArgs {
+    #[arg(short, long, help = "server to connect to on start")]
+    pub connect: Option<SocketAddr>,
+
+    #[arg(long, help = "exchange Hello when connecting on start")]
+    pub handshake: bool,
+
+    #[arg(long, help = "run the commands in this file instead, - reads stdin")]
+    pub script: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub limits: NetLimitArgs,
+}
+
+// logs frames, used by serve
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NetLogHandler {
+    pub echo: bool,
+}
+
+// prints frames to stdout, used by listen
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NetPrintHandler {
+    pub hex: bool,
+}
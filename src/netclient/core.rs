@@ -0,0 +1,342 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::{
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use futures::{stream::SplitSink, Sink, SinkExt, Stream, StreamExt};
+use tokio::{
+    net::{TcpStream, ToSocketAddrs},
+    sync::mpsc,
+    time::{self, Instant},
+};
+use tokio_util::{codec::Framed, sync::PollSender};
+
+use crate::{
+    netclient::{
+        consts::NETCLIENT_SEND_QUEUE_FRAMES,
+        types::{NetClientReceiver, NetClientSender, NetStreamClient},
+    },
+    netcodec::types::NetFrameCodec,
+    netframe::types::{NetFrame, NetFrameTag},
+    netsession::{
+        error::NetSessionError,
+        types::{NetGoodbyeReason, NetHello, NetKeepalive, NetReset, NetSession},
+    },
+    netstream::{
+        error::{NetStreamErr, NetStreamErrorType},
+        types::{NetStreamConfig, NetStreamMode},
+    },
+};
+
+impl NetStreamClient {
+    pub async fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        Self::with_codec(address, NetFrameCodec::default()).await
+    }
+
+    // codec decides the frame size limit, the checksum mode and the
+    // delimiter, all have to match the server
+    pub async fn with_codec<A: ToSocketAddrs>(
+        address: A,
+        codec: NetFrameCodec,
+    ) -> io::Result<Self> {
+        let socket = TcpStream::connect(address).await?;
+        socket.set_nodelay(true)?;
+        Ok(Self::from_socket(socket, codec))
+    }
+
+    pub fn from_socket(
+        socket: TcpStream,
+        codec: NetFrameCodec,
+    ) -> Self {
+        let config = NetStreamConfig {
+            max_frame_size: codec.max_frame_size,
+            checksum: codec.checksum,
+            delimiter: codec.delimiter,
+            mode: codec.mode,
+            ..Default::default()
+        };
+        Self {
+            framed: Framed::new(socket, codec),
+            session: NetSession::new(NetHello::with_config(&config)),
+            pong: None,
+            flushing: false,
+            errored: false,
+        }
+    }
+
+    // sends Hello and waits for the one from the server, which has to be
+    // the first frame it sends back
+    pub async fn handshake(&mut self) -> Result<&NetSession, NetSessionError> {
+        self.send_frame(self.session.hello()).await?;
+        let frame = match self.next_frame().await {
+            Some(frame) => frame?,
+            None => return Err(NetStreamErr::new(NetStreamErrorType::StreamClosed).into()),
+        };
+        match frame.kind() {
+            NetFrameTag::Hello => {
+                self.session.accept(&frame)?;
+                Ok(&self.session)
+            }
+            NetFrameTag::Goodbye => {
+                Err(NetSessionError::Closed(self.session.closed_by_peer(&frame)))
+            }
+            _ => Err(NetSessionError::HandshakeMissing),
+        }
+    }
+
+    // asks the server to drop the fragments in flight and waits for the
+    // acknowledgement. Frames the server sent before it, including those
+    // already read but not taken yet, are returned, Pings are answered
+    // meanwhile. After a protocol error bytes that do not decode are
+    // skipped until the acknowledgement arrives.
+    pub async fn reset(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Vec<NetFrame>, NetSessionError> {
+        let mode = self.framed.codec().mode;
+        if self.errored {
+            self.framed.codec_mut().mode = NetStreamMode::Resync;
+        }
+        let result = self.wait_reset(timeout).await;
+        self.framed.codec_mut().mode = mode;
+        result
+    }
+
+    async fn wait_reset(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Vec<NetFrame>, NetSessionError> {
+        let deadline = Instant::now() + timeout;
+        self.send_frame(NetReset::Request.to_frame()).await?;
+        let mut skipped = Vec::new();
+        loop {
+            // after an error the framed stream ends once, then reads again
+            let resuming = self.errored;
+            let frame = match time::timeout_at(deadline, self.next_frame()).await {
+                Ok(Some(frame)) => frame?,
+                Ok(None) if resuming => continue,
+                Ok(None) => return Err(NetStreamErr::new(NetStreamErrorType::StreamClosed).into()),
+                Err(_) => return Err(NetSessionError::ResetTimeout),
+            };
+            match frame.kind() {
+                NetFrameTag::Reset if NetReset::from_frame(&frame) == Ok(NetReset::Ack) => {
+                    return Ok(skipped);
+                }
+                NetFrameTag::Goodbye => {
+                    return Err(NetSessionError::Closed(self.session.closed_by_peer(&frame)));
+                }
+                _ => skipped.push(frame),
+            }
+        }
+    }
+
+    // says Goodbye to the server and closes the connection
+    pub async fn goodbye(
+        &mut self,
+        reason: NetGoodbyeReason,
+    ) -> Result<(), NetStreamErr> {
+        let frame = self.session.close(reason);
+        self.send_frame(frame).await?;
+        self.close().await
+    }
+
+    pub async fn send_frame(
+        &mut self,
+        frame: NetFrame,
+    ) -> Result<(), NetStreamErr> {
+        self.framed.send(frame).await
+    }
+
+    // next decoded frame, None once the server closed the connection
+    pub async fn next_frame(&mut self) -> Option<Result<NetFrame, NetStreamErr>> {
+        self.next().await
+    }
+
+    // flushes pending frames and shuts the write half down
+    pub async fn close(&mut self) -> Result<(), NetStreamErr> {
+        self.framed.close().await
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.framed.get_ref().local_addr()
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.framed.get_ref().peer_addr()
+    }
+
+    // has to be called from within the runtime, it spawns the task
+    // writing the frames of both halves
+    pub fn split(self) -> (NetClientSender, NetClientReceiver) {
+        let (sink, stream) = self.framed.split();
+        let (frames, queued) = mpsc::channel(NETCLIENT_SEND_QUEUE_FRAMES);
+        let (pongs, answers) = mpsc::unbounded_channel();
+        tokio::spawn(write_frames(sink, self.pong, queued, answers));
+        let sender = NetClientSender {
+            frames: PollSender::new(frames),
+        };
+        let receiver = NetClientReceiver {
+            stream,
+            pongs,
+        };
+        (sender, receiver)
+    }
+
+    // moves the Pong into the write buffer and flushes it, without
+    // waiting for either, the next poll tries again
+    fn poll_pong(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Result<(), NetStreamErr> {
+        if self.pong.is_some() {
+            if let Poll::Ready(ready) = self.framed.poll_ready_unpin(cx) {
+                ready?;
+                if let Some(pong) = self.pong.take() {
+                    self.framed.start_send_unpin(pong)?;
+                    self.flushing = true;
+                }
+            }
+        }
+        if self.flushing {
+            if let Poll::Ready(flushed) = self.framed.poll_flush_unpin(cx) {
+                flushed?;
+                self.flushing = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Stream for NetStreamClient {
+    type Item = Result<NetFrame, NetStreamErr>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Err(err) = self.poll_pong(cx) {
+                return Poll::Ready(Some(Err(err)));
+            }
+            match ready!(self.framed.poll_next_unpin(cx)) {
+                // only the latest one matters if the socket is backed up
+                Some(Ok(frame)) if frame.kind() == NetFrameTag::Ping => {
+                    self.pong = Some(NetKeepalive::reply(&frame));
+                }
+                item => {
+                    self.errored = matches!(item, Some(Err(_)));
+                    return Poll::Ready(item);
+                }
+            }
+        }
+    }
+}
+
+impl Stream for NetClientReceiver {
+    type Item = Result<NetFrame, NetStreamErr>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            match ready!(self.stream.poll_next_unpin(cx)) {
+                Some(Ok(frame)) if frame.kind() == NetFrameTag::Ping => {
+                    // writer is gone only if the connection is
+                    let _ = self.pongs.send(NetKeepalive::reply(&frame));
+                }
+                item => return Poll::Ready(item),
+            }
+        }
+    }
+}
+
+impl Sink<NetFrame> for NetClientSender {
+    type Error = NetStreamErr;
+
+    fn poll_ready(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.frames.poll_ready_unpin(cx).map_err(closed)
+    }
+
+    fn start_send(
+        mut self: Pin<&mut Self>,
+        frame: NetFrame,
+    ) -> Result<(), Self::Error> {
+        self.frames.start_send_unpin(frame).map_err(closed)
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.frames.poll_flush_unpin(cx).map_err(closed)
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.frames.poll_close_unpin(cx).map_err(closed)
+    }
+}
+
+fn closed<T>(_: T) -> NetStreamErr {
+    NetStreamErr::new(NetStreamErrorType::StreamClosed)
+}
+
+// writes the frames of both split halves, Pongs first. Shuts the write
+// half down once the sender is dropped or closed, Pings are not answered
+// from then on.
+async fn write_frames(
+    mut sink: SplitSink<Framed<TcpStream, NetFrameCodec>, NetFrame>,
+    pong: Option<NetFrame>,
+    mut frames: mpsc::Receiver<NetFrame>,
+    mut pongs: mpsc::UnboundedReceiver<NetFrame>,
+) {
+    // the client might have left a Pong behind, queued or in the buffer
+    let pending = match pong {
+        Some(pong) => sink.send(pong).await,
+        None => sink.flush().await,
+    };
+    if let Err(err) = pending {
+        tracing::warn!("client send failed: {}", err);
+        return;
+    }
+    loop {
+        let frame = tokio::select! {
+            biased;
+            Some(pong) = pongs.recv() => pong,
+            frame = frames.recv() => match frame {
+                Some(frame) => frame,
+                None => break,
+            },
+        };
+        if let Err(err) = sink.send(frame).await {
+            tracing::warn!("client send failed: {}", err);
+            return;
+        }
+    }
+    let _ = sink.close().await;
+}
@@ -0,0 +1,264 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::time::Duration;
+
+use futures::{SinkExt, StreamExt};
+use tokio::{io::AsyncWriteExt, net::TcpListener};
+
+use crate::{
+    netclient::types::NetStreamClient,
+    netcodec::types::NetFrameCodec,
+    netframe::types::{NetFrame, NetFrameChecksum, NetFrameTag},
+    netsession::{error::NetSessionError, types::NetReset},
+    netstream::{error::NetStreamErrorType, types::NetStreamMode},
+};
+
+
+// echo server speaking the wire format through the codec
+async fn echo_server(codec: NetFrameCodec) -> std::net::SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut framed = tokio_util::codec::Framed::new(socket, codec);
+        while let Some(Ok(frame)) = framed.next().await {
+            framed.send(frame).await.unwrap();
+        }
+    });
+    address
+}
+
+#[tokio::test]
+async fn netclient_ok_send_and_receive() {
+    let address = echo_server(NetFrameCodec::default()).await;
+    let mut client = NetStreamClient::connect(address).await.unwrap();
+    let frames: Vec<NetFrame> = (0..4u8)
+        .map(|i| NetFrame::with_kind(NetFrameTag::SingleMessage, vec![i; 100]))
+        .collect();
+    for frame in frames.iter() {
+        client.send_frame(frame.clone()).await.unwrap();
+    }
+    for frame in frames.iter() {
+        assert_eq!(client.next_frame().await, Some(Ok(frame.clone())));
+    }
+    client.close().await.unwrap();
+    assert_eq!(client.next_frame().await, None);
+}
+
+#[tokio::test]
+async fn netclient_ok_split_with_checksum() {
+    let codec = NetFrameCodec::default().with_checksum(NetFrameChecksum::Crc32);
+    let address = echo_server(codec.clone()).await;
+    let client = NetStreamClient::with_codec(address, codec).await.unwrap();
+    let (mut sender, receiver) = client.split();
+    let frame = NetFrame::with_kind(NetFrameTag::SingleMessage, vec![0x42; 1000]);
+    let sending = tokio::spawn({
+        let frame = frame.clone();
+        async move {
+            for _ in 0..3 {
+                sender.send(frame.clone()).await.unwrap();
+            }
+        }
+    });
+    let received: Vec<_> = receiver.take(3).collect().await;
+    sending.await.unwrap();
+    assert_eq!(
+        received,
+        vec![Ok(frame.clone()), Ok(frame.clone()), Ok(frame)]
+    );
+}
+
+#[tokio::test]
+async fn netclient_failure_protocol_error() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        socket.write_all(&[0x01, 0x01, 0x00, 0x00]).await.unwrap();
+    });
+    let mut client = NetStreamClient::connect(address).await.unwrap();
+    let err = client.next_frame().await.unwrap().unwrap_err();
+    assert_eq!(err.category, NetStreamErrorType::FramingDelimiterMismatch);
+}
+
+// sends a Ping and a message, returns what the client answered
+async fn ping_server() -> (std::net::SocketAddr, tokio::task::JoinHandle<NetFrame>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut framed = tokio_util::codec::Framed::new(socket, NetFrameCodec::default());
+        framed
+            .send(NetFrame::with_kind(NetFrameTag::Ping, vec![0, 0, 0, 7]))
+            .await
+            .unwrap();
+        framed
+            .send(NetFrame::with_kind(NetFrameTag::SingleMessage, "after"))
+            .await
+            .unwrap();
+        framed.next().await.unwrap().unwrap()
+    });
+    (address, server)
+}
+
+#[tokio::test]
+async fn netclient_ok_answers_ping() {
+    let (address, server) = ping_server().await;
+    let mut client = NetStreamClient::connect(address).await.unwrap();
+    // the Ping is not handed out
+    let frame = client.next_frame().await.unwrap().unwrap();
+    assert_eq!(frame.kind(), NetFrameTag::SingleMessage);
+    assert_eq!(
+        server.await.unwrap(),
+        NetFrame::with_kind(NetFrameTag::Pong, vec![0, 0, 0, 7])
+    );
+}
+
+#[tokio::test]
+async fn netclient_ok_split_answers_ping() {
+    let (address, server) = ping_server().await;
+    let client = NetStreamClient::connect(address).await.unwrap();
+    // sender is idle, the receiver answers through the writer task
+    let (_sender, mut receiver) = client.split();
+    let frame = receiver.next().await.unwrap().unwrap();
+    assert_eq!(frame.kind(), NetFrameTag::SingleMessage);
+    assert_eq!(
+        server.await.unwrap(),
+        NetFrame::with_kind(NetFrameTag::Pong, vec![0, 0, 0, 7])
+    );
+}
+
+// leaves half a frame in the client's read buffer, then answers a Reset
+// with a message and a Ping first, the Ack once the Ping was answered
+async fn reset_server(ack: bool) -> std::net::SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut framed = tokio_util::codec::Framed::new(socket, NetFrameCodec::default());
+        let request = framed.next().await.unwrap().unwrap();
+        assert_eq!(NetReset::from_frame(&request), Ok(NetReset::Request));
+        framed
+            .send(NetFrame::with_kind(NetFrameTag::SingleMessage, "before"))
+            .await
+            .unwrap();
+        framed
+            .send(NetFrame::with_kind(NetFrameTag::Ping, vec![0, 0, 0, 7]))
+            .await
+            .unwrap();
+        let pong = framed.next().await.unwrap().unwrap();
+        assert_eq!(pong.kind(), NetFrameTag::Pong);
+        if ack {
+            framed.send(NetReset::Ack.to_frame()).await.unwrap();
+        }
+        // keeps the connection open until the client is done
+        framed.next().await;
+    });
+    address
+}
+
+#[tokio::test]
+async fn netclient_ok_reset() {
+    let address = reset_server(true).await;
+    let mut client = NetStreamClient::connect(address).await.unwrap();
+    assert_eq!(
+        client.reset(Duration::from_secs(5)).await,
+        Ok(vec![NetFrame::with_kind(
+            NetFrameTag::SingleMessage,
+            "before"
+        )])
+    );
+}
+
+#[tokio::test]
+async fn netclient_ok_reset_keeps_buffered_frames() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut framed = tokio_util::codec::Framed::new(socket, NetFrameCodec::default());
+        // three frames in a single write, the client reads them at once
+        for data in ["first", "second", "third"] {
+            framed
+                .feed(NetFrame::with_kind(NetFrameTag::SingleMessage, data))
+                .await
+                .unwrap();
+        }
+        framed.flush().await.unwrap();
+        let request = framed.next().await.unwrap().unwrap();
+        assert_eq!(NetReset::from_frame(&request), Ok(NetReset::Request));
+        framed.send(NetReset::Ack.to_frame()).await.unwrap();
+        framed.next().await;
+    });
+    let mut client = NetStreamClient::connect(address).await.unwrap();
+    assert_eq!(
+        client.next_frame().await,
+        Some(Ok(NetFrame::with_kind(NetFrameTag::SingleMessage, "first")))
+    );
+    assert_eq!(
+        client.reset(Duration::from_secs(5)).await,
+        Ok(vec![
+            NetFrame::with_kind(NetFrameTag::SingleMessage, "second"),
+            NetFrame::with_kind(NetFrameTag::SingleMessage, "third"),
+        ])
+    );
+}
+
+#[tokio::test]
+async fn netclient_failure_reset_timeout() {
+    let address = reset_server(false).await;
+    let mut client = NetStreamClient::connect(address).await.unwrap();
+    assert_eq!(
+        client.reset(Duration::from_millis(200)).await,
+        Err(NetSessionError::ResetTimeout)
+    );
+}
+
+#[tokio::test]
+async fn netclient_ok_reset_after_protocol_error() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        socket.write_all(&[0x01, 0x01, 0x01, 0x01]).await.unwrap();
+        let mut framed = tokio_util::codec::Framed::new(socket, NetFrameCodec::default());
+        let request = framed.next().await.unwrap().unwrap();
+        assert_eq!(NetReset::from_frame(&request), Ok(NetReset::Request));
+        // rest of a frame cut in half by the reset, followed by the Ack
+        framed
+            .get_mut()
+            .write_all(&[0x42, 0x42, 0x42])
+            .await
+            .unwrap();
+        framed.send(NetReset::Ack.to_frame()).await.unwrap();
+        framed
+            .send(NetFrame::with_kind(NetFrameTag::SingleMessage, "after"))
+            .await
+            .unwrap();
+        framed.next().await;
+    });
+    let mut client = NetStreamClient::connect(address).await.unwrap();
+    let err = client.next_frame().await.unwrap().unwrap_err();
+    assert_eq!(err.category, NetStreamErrorType::FramingDelimiterMismatch);
+    assert_eq!(client.reset(Duration::from_secs(5)).await, Ok(vec![]));
+    // stream is strict again and reads on
+    assert_eq!(client.framed.codec().mode, NetStreamMode::Strict);
+    assert_eq!(
+        client.next_frame().await,
+        Some(Ok(NetFrame::with_kind(NetFrameTag::SingleMessage, "after")))
+    );
+}
@@ -0,0 +1,63 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use futures::stream::SplitStream;
+use tokio::{net::TcpStream, sync::mpsc};
+use tokio_util::{codec::Framed, sync::PollSender};
+
+use crate::{
+    netcodec::types::NetFrameCodec,
+    netframe::types::NetFrame,
+    netsession::types::NetSession,
+};
+
+
+// tcp client speaking the netframe wire format. Received frames are read
+// with next_frame() or by using the client as a
+// Stream<Item = Result<NetFrame, NetStreamErr>>, protocol failures
+// surface as NetStreamErr items. Pings are answered while reading and
+// never returned.
+#[derive(Debug)]
+pub struct NetStreamClient {
+    pub framed: Framed<TcpStream, NetFrameCodec>,
+    // filled in by handshake(), servers that do not require it can be
+    // talked to without one
+    pub session: NetSession,
+    // Pong waiting for room in the write buffer
+    pub pong: Option<NetFrame>,
+    // Pong is in the write buffer but not flushed yet
+    pub flushing: bool,
+    // last item was an error, the framed stream returns None once before
+    // it reads again
+    pub errored: bool,
+}
+
+// halves of a split client, so frames can be sent and received from
+// different tasks. The write half is owned by a task both of them feed,
+// the receiver answers Pings through it even while the sender is busy.
+// Sink<NetFrame, Error = NetStreamErr>, send errors are logged by the
+// task and show up as StreamClosed.
+#[derive(Debug)]
+pub struct NetClientSender {
+    pub frames: PollSender<NetFrame>,
+}
+
+// Stream<Item = Result<NetFrame, NetStreamErr>> without the Pings
+#[derive(Debug)]
+pub struct NetClientReceiver {
+    pub stream: SplitStream<Framed<TcpStream, NetFrameCodec>>,
+    pub pongs: mpsc::UnboundedSender<NetFrame>,
+}
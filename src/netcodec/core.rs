@@ -0,0 +1,166 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{
+    netcodec::types::NetFrameCodec,
+    netframe::{
+        consts::{NETFRAME_DELIMITER, NETFRAME_EXTENDED_MAX_DATA_SIZE, NETFRAME_MAX_DATA_SIZE},
+        types::{NetFrame, NetFrameChecksum},
+    },
+    netstream::{
+        error::{NetStreamErr, NetStreamErrorType},
+        types::{NetStreamConfig, NetStreamMode},
+    },
+};
+
+impl NetFrameCodec {
+    pub fn new(max_frame_size: usize) -> Self {
+        Self {
+            max_frame_size: max_frame_size.min(NETFRAME_EXTENDED_MAX_DATA_SIZE),
+            checksum: NetFrameChecksum::None,
+            delimiter: NETFRAME_DELIMITER,
+            mode: NetStreamMode::Strict,
+        }
+    }
+
+    pub fn with_checksum(
+        mut self,
+        checksum: NetFrameChecksum,
+    ) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    pub fn with_delimiter(
+        mut self,
+        delimiter: u8,
+    ) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn with_mode(
+        mut self,
+        mode: NetStreamMode,
+    ) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    // drops the byte that failed to decode and everything up to the next
+    // delimiter, the same way NetStream::resync() does it
+    fn skip_garbage(
+        &self,
+        src: &mut BytesMut,
+    ) -> usize {
+        let next = src
+            .iter()
+            .skip(1)
+            .position(|byte| *byte == self.delimiter)
+            .map_or(src.len(), |position| position + 1);
+        src.advance(next);
+        next
+    }
+}
+
+// codec speaking to a peer configured like the stream
+impl From<&NetStreamConfig> for NetFrameCodec {
+    fn from(config: &NetStreamConfig) -> Self {
+        NetFrameCodec::new(config.max_frame_size)
+            .with_checksum(config.checksum)
+            .with_delimiter(config.delimiter)
+            .with_mode(config.mode)
+    }
+}
+
+impl Default for NetFrameCodec {
+    fn default() -> Self {
+        Self::new(NETFRAME_MAX_DATA_SIZE)
+    }
+}
+
+impl Decoder for NetFrameCodec {
+    type Error = NetStreamErr;
+    type Item = NetFrame;
+
+    fn decode(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<Self::Item>, Self::Error> {
+        let decoded = loop {
+            match NetFrame::decode_delimited(
+                src,
+                self.max_frame_size,
+                self.checksum,
+                self.delimiter,
+            ) {
+                Err(err) if self.mode == NetStreamMode::Resync => {
+                    let skipped = self.skip_garbage(src);
+                    tracing::debug!("skipped {} bytes: {}", skipped, err);
+                }
+                decoded => break decoded?,
+            }
+        };
+        match decoded {
+            Some(frame) => Ok(Some(frame)),
+            None => {
+                // make room for the rest of the frame, so it is read in one go
+                if let Ok(metadata) =
+                    NetFrame::get_metadata_delimited(src, self.max_frame_size, self.delimiter)
+                {
+                    let size =
+                        metadata.header_size() + metadata.size as usize + self.checksum.size();
+                    src.reserve(size.saturating_sub(src.len()));
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl Encoder<NetFrame> for NetFrameCodec {
+    type Error = NetStreamErr;
+
+    fn encode(
+        &mut self,
+        item: NetFrame,
+        dst: &mut BytesMut,
+    ) -> Result<(), Self::Error> {
+        if item.data.len() > self.max_frame_size {
+            return Err(NetStreamErr::new(NetStreamErrorType::StreamMessageTooLong));
+        }
+
+        let start = dst.len();
+        dst.resize(
+            start + item.encoded_len_delimited(self.max_frame_size, self.checksum),
+            0,
+        );
+        if let Err(err) = item.encode_into_delimited(
+            &mut dst[start..],
+            self.max_frame_size,
+            self.checksum,
+            self.delimiter,
+        ) {
+            dst.truncate(start);
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use bytes::{Bytes, BytesMut};
+use futures::{SinkExt, StreamExt};
+use tokio_util::codec::{Decoder, Encoder, Framed};
+
+use crate::{
+    netcodec::types::NetFrameCodec,
+    netframe::{consts::NETFRAME_MAX_DATA_SIZE, types::NetFrame},
+    netstream::{
+        error::{NetStreamErr, NetStreamErrorType},
+        types::NetStreamMode,
+    },
+};
+
+
+#[test]
+fn codec_ok_decode_partial_then_complete() {
+    let mut codec = NetFrameCodec::default();
+    // header only, data is still on the way
+    let mut buffer = BytesMut::from(&[0x00, 0x01, 0x00, 0x02][..]);
+    assert_eq!(codec.decode(&mut buffer), Ok(None));
+    assert_eq!(buffer.len(), 4);
+    // data arrives together with the start of the next frame
+    buffer.extend_from_slice(&[0xAB, 0xCD, 0x00, 0x02]);
+    assert_eq!(
+        codec.decode(&mut buffer),
+        Ok(Some(NetFrame {
+            tag: 0x01,
+            data: Bytes::from_static(&[0xAB, 0xCD]),
+        }))
+    );
+    assert_eq!(codec.decode(&mut buffer), Ok(None));
+    assert_eq!(&buffer[..], &[0x00, 0x02]);
+}
+
+#[test]
+fn codec_failure_delimiter_mismatch() {
+    let mut codec = NetFrameCodec::default();
+    let mut buffer = BytesMut::from(&[0x01, 0x01, 0x00, 0x00][..]);
+    assert_eq!(
+        codec.decode(&mut buffer),
+        Err(NetStreamErr::new(
+            NetStreamErrorType::FramingDelimiterMismatch
+        ))
+    );
+}
+
+#[test]
+fn codec_ok_resync_skips_garbage() {
+    let mut codec = NetFrameCodec::default().with_mode(NetStreamMode::Resync);
+    let mut buffer = BytesMut::from(&[0x01, 0x42, 0x00, 0x01, 0x00, 0x01, 0xAB][..]);
+    assert_eq!(
+        codec.decode(&mut buffer),
+        Ok(Some(NetFrame {
+            tag: 0x01,
+            data: Bytes::from_static(&[0xAB]),
+        }))
+    );
+    assert!(buffer.is_empty());
+    // nothing but garbage is dropped as a whole
+    let mut buffer = BytesMut::from(&[0x01, 0x42, 0x42, 0x42][..]);
+    assert_eq!(codec.decode(&mut buffer), Ok(None));
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn codec_failure_frame_above_max_size() {
+    let mut codec = NetFrameCodec::new(2);
+    // header declares 3 bytes of data
+    let mut buffer = BytesMut::from(&[0x00, 0x01, 0x00, 0x03][..]);
+    assert_eq!(
+        codec.decode(&mut buffer),
+        Err(NetStreamErr::new(NetStreamErrorType::StreamMessageTooLong))
+    );
+    let mut buffer = BytesMut::new();
+    assert_eq!(
+        codec.encode(NetFrame::new(0x01, vec![0x00; 3]), &mut buffer),
+        Err(NetStreamErr::new(NetStreamErrorType::StreamMessageTooLong))
+    );
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn codec_ok_encode_appends_frames() {
+    let mut codec = NetFrameCodec::default();
+    let mut buffer = BytesMut::new();
+    assert_eq!(
+        codec.encode(NetFrame::new(0x01, vec![0xAB]), &mut buffer),
+        Ok(())
+    );
+    assert_eq!(
+        codec.encode(NetFrame::new(0x02, vec![]), &mut buffer),
+        Ok(())
+    );
+    assert_eq!(
+        &buffer[..],
+        &[0x00, 0x01, 0x00, 0x01, 0xAB, 0x00, 0x02, 0x00, 0x00]
+    );
+}
+
+#[tokio::test]
+async fn codec_ok_framed_round_trip() {
+    let (client, server) = tokio::io::duplex(64);
+    let mut client = Framed::new(client, NetFrameCodec::default());
+    let mut server = Framed::new(server, NetFrameCodec::default());
+    let frames: Vec<NetFrame> = (0..16u8).map(|i| NetFrame::new(i, vec![i; 100])).collect();
+    let sent = frames.clone();
+    let sender = tokio::spawn(async move {
+        for frame in sent {
+            client.send(frame).await.unwrap();
+        }
+    });
+    for frame in frames {
+        assert_eq!(server.next().await, Some(Ok(frame)));
+    }
+    sender.await.unwrap();
+    // client is dropped, stream ends
+    assert_eq!(server.next().await, None);
+}
+
+#[test]
+fn codec_ok_extended_frame_when_enabled() {
+    let frame = NetFrame::new(0x01, vec![0xAB; NETFRAME_MAX_DATA_SIZE + 1]);
+    let mut buffer = BytesMut::new();
+    // default codec sticks to the short header
+    assert_eq!(
+        NetFrameCodec::default().encode(frame.clone(), &mut buffer),
+        Err(NetStreamErr::new(NetStreamErrorType::StreamMessageTooLong))
+    );
+    let mut codec = NetFrameCodec::new(2 * NETFRAME_MAX_DATA_SIZE);
+    assert_eq!(codec.encode(frame.clone(), &mut buffer), Ok(()));
+    assert_eq!(codec.decode(&mut buffer), Ok(Some(frame)));
+}
+
+#[test]
+fn codec_ok_custom_delimiter() {
+    let mut codec = NetFrameCodec::default().with_delimiter(0xAA);
+    let frame = NetFrame::new(0x01, vec![0x11; 4]);
+    let mut buffer = BytesMut::new();
+    codec.encode(frame.clone(), &mut buffer).unwrap();
+    assert_eq!(buffer[0], 0xAA);
+    assert_eq!(codec.decode(&mut buffer), Ok(Some(frame.clone())));
+    // other side has to use the same one
+    let mut buffer = BytesMut::from(&frame.encode().unwrap()[..]);
+    assert_eq!(
+        codec.decode(&mut buffer),
+        Err(NetStreamErr::new(
+            NetStreamErrorType::FramingDelimiterMismatch
+        ))
+    );
+}
@@ -0,0 +1,35 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use crate::{netframe::types::NetFrameChecksum, netstream::types::NetStreamMode};
+
+
+// tokio_util codec for the netframe wire format, use it with
+// Framed::new(socket, NetFrameCodec::default()) to get a
+// Stream<Item = Result<NetFrame, NetStreamErr>> + Sink<NetFrame>
+//
+// frames are split off the read buffer the same way NetStream does it,
+// delimiter mismatch and frames above max_frame_size are errors, unless
+// the codec is in NetStreamMode::Resync, which skips the garbage.
+// Extended length frames are only read and written if max_frame_size is
+// raised above NETFRAME_MAX_DATA_SIZE.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetFrameCodec {
+    pub max_frame_size: usize,
+    pub checksum: NetFrameChecksum,
+    pub delimiter: u8,
+    pub mode: NetStreamMode,
+}
@@ -0,0 +1,20 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// environment variables overriding the file are named
+// NETSTREAM_<KEY> for top level keys and NETSTREAM_<SECTION>_<KEY>
+// for the others, i.e. NETSTREAM_BIND or NETSTREAM_STREAM_CHECKSUM
+pub const NETCONFIG_ENV_PREFIX: &str = "NETSTREAM_";
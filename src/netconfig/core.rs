@@ -0,0 +1,272 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::{ffi::OsStr, fs, path::Path, str::FromStr, time::Duration};
+
+use serde::de::{value, DeserializeOwned, IntoDeserializer};
+
+use super::{
+    consts::NETCONFIG_ENV_PREFIX,
+    error::NetConfigError,
+    types::{NetConfig, NetServerSection},
+};
+use crate::{
+    logger::types::LogRotation,
+    netfragment::types::NetReassemblerConfig,
+    netframe::consts::NETFRAME_EXTENDED_MAX_DATA_SIZE,
+    netserver::{consts::NETSERVER_DEFAULT_ADDRESS, types::NetServerConfig},
+    netsession::types::NetKeepaliveConfig,
+};
+
+impl Default for NetConfig {
+    fn default() -> Self {
+        Self {
+            // constant, always parses
+            bind: NETSERVER_DEFAULT_ADDRESS.parse().unwrap(),
+            stream: Default::default(),
+            server: Default::default(),
+            log: Default::default(),
+        }
+    }
+}
+
+impl Default for NetServerSection {
+    fn default() -> Self {
+        let defaults = NetServerConfig::default();
+        let keepalive = NetKeepaliveConfig::default();
+        Self {
+            max_connections: defaults.max_connections,
+            max_outbound_bytes: defaults.max_outbound_bytes,
+            outbound_policy: defaults.outbound_policy,
+            require_handshake: defaults.require_handshake,
+            control: defaults.control,
+            control_admins: defaults.control_admins,
+            keepalive_interval_millis: keepalive.interval.as_millis() as u64,
+            keepalive_max_missed: keepalive.max_missed,
+            max_message_size: defaults.reassembly.max_message_size,
+            max_pending_messages: defaults.reassembly.max_pending_messages,
+            max_pending_bytes: defaults.reassembly.max_pending_bytes,
+            reassembly_timeout_millis: defaults.reassembly.timeout.as_millis() as u64,
+            read_chunk_size: defaults.read_chunk_size,
+            events_capacity: defaults.events_capacity,
+        }
+    }
+}
+
+impl NetConfig {
+    // TOML unless the file has the .json extension
+    pub fn load(path: &Path) -> Result<Self, NetConfigError> {
+        let text = fs::read_to_string(path).map_err(|err| {
+            NetConfigError::Read {
+                path: path.display().to_string(),
+                message: err.to_string(),
+            }
+        })?;
+        let parsed = match path.extension().and_then(OsStr::to_str) {
+            Some("json") => serde_json::from_str(&text).map_err(|err| err.to_string()),
+            _ => toml::from_str(&text).map_err(|err| err.to_string()),
+        };
+        parsed.map_err(|message| {
+            NetConfigError::Parse {
+                path: path.display().to_string(),
+                message,
+            }
+        })
+    }
+
+    // applies NETSTREAM_* variables, pass std::env::vars(). Variables
+    // with the prefix that do not name a key are ignored and returned,
+    // the logger is usually configured by them so it can not warn yet.
+    pub fn apply_env(
+        &mut self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Vec<String>, NetConfigError> {
+        let mut ignored = Vec::new();
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(NETCONFIG_ENV_PREFIX) else {
+                continue;
+            };
+            let stream = &mut self.stream;
+            let server = &mut self.server;
+            let log = &mut self.log;
+            match key {
+                "BIND" => self.bind = parse(&name, &value)?,
+                "STREAM_MAX_BUFFERED_BYTES" => stream.max_buffered_bytes = parse(&name, &value)?,
+                "STREAM_MAX_QUEUED_FRAMES" => stream.max_queued_frames = parse(&name, &value)?,
+                "STREAM_MAX_FRAME_SIZE" => stream.max_frame_size = parse(&name, &value)?,
+                "STREAM_CHECKSUM" => stream.checksum = parse_enum(&name, &value)?,
+                "STREAM_DELIMITER" => stream.delimiter = parse(&name, &value)?,
+                "STREAM_MODE" => stream.mode = parse_enum(&name, &value)?,
+                "SERVER_MAX_CONNECTIONS" => server.max_connections = Some(parse(&name, &value)?),
+                "SERVER_MAX_OUTBOUND_BYTES" => server.max_outbound_bytes = parse(&name, &value)?,
+                "SERVER_OUTBOUND_POLICY" => server.outbound_policy = parse_enum(&name, &value)?,
+                "SERVER_REQUIRE_HANDSHAKE" => server.require_handshake = parse(&name, &value)?,
+                "SERVER_CONTROL" => server.control = parse(&name, &value)?,
+                "SERVER_CONTROL_ADMINS" => server.control_admins = parse_list(&name, &value)?,
+                "SERVER_KEEPALIVE_INTERVAL_MILLIS" => {
+                    server.keepalive_interval_millis = parse(&name, &value)?
+                }
+                "SERVER_KEEPALIVE_MAX_MISSED" => {
+                    server.keepalive_max_missed = parse(&name, &value)?
+                }
+                "SERVER_MAX_MESSAGE_SIZE" => server.max_message_size = parse(&name, &value)?,
+                "SERVER_MAX_PENDING_MESSAGES" => {
+                    server.max_pending_messages = parse(&name, &value)?
+                }
+                "SERVER_MAX_PENDING_BYTES" => server.max_pending_bytes = parse(&name, &value)?,
+                "SERVER_REASSEMBLY_TIMEOUT_MILLIS" => {
+                    server.reassembly_timeout_millis = parse(&name, &value)?
+                }
+                "SERVER_READ_CHUNK_SIZE" => server.read_chunk_size = parse(&name, &value)?,
+                "SERVER_EVENTS_CAPACITY" => server.events_capacity = parse(&name, &value)?,
+                "LOG_TARGET" => log.target = parse_enum(&name, &value)?,
+                "LOG_FORMAT" => log.format = parse_enum(&name, &value)?,
+                "LOG_ANSI" => log.ansi = parse(&name, &value)?,
+                "LOG_DIRECTORY" => log.directory = parse(&name, &value)?,
+                "LOG_FILE_NAME" => log.file_name = parse(&name, &value)?,
+                "LOG_ROTATION" => log.rotation = parse_enum(&name, &value)?,
+                "LOG_MAX_FILE_SIZE" => log.max_file_size = parse(&name, &value)?,
+                "LOG_MAX_FILES" => log.max_files = parse(&name, &value)?,
+                _ => ignored.push(name),
+            }
+        }
+        Ok(ignored)
+    }
+
+    // checks the final configuration, after all the layers were applied
+    pub fn validate(&self) -> Result<(), NetConfigError> {
+        let stream = &self.stream;
+        let server = &self.server;
+        // same checks as NetStreamBuilder::build(), spelled out for the user
+        if stream.validate().is_err() {
+            return Err(invalid(format!(
+                "stream limits can not hold a frame: max_frame_size has to be in 1..={}, \
+                 max_buffered_bytes at least {} and max_queued_frames above 0",
+                NETFRAME_EXTENDED_MAX_DATA_SIZE,
+                stream.min_buffered_bytes()
+            )));
+        }
+        if server.max_connections == Some(0) {
+            return Err(invalid("server.max_connections can not be 0"));
+        }
+        if server.max_outbound_bytes == 0 {
+            return Err(invalid("server.max_outbound_bytes can not be 0"));
+        }
+        if server.keepalive_interval_millis > 0 && server.keepalive_max_missed == 0 {
+            return Err(invalid("server.keepalive_max_missed can not be 0"));
+        }
+        if server.max_message_size == 0 {
+            return Err(invalid("server.max_message_size can not be 0"));
+        }
+        if server.max_pending_messages == 0 {
+            return Err(invalid("server.max_pending_messages can not be 0"));
+        }
+        if server.max_pending_bytes == 0 {
+            return Err(invalid("server.max_pending_bytes can not be 0"));
+        }
+        if server.reassembly_timeout_millis == 0 {
+            return Err(invalid("server.reassembly_timeout_millis can not be 0"));
+        }
+        if server.read_chunk_size == 0 {
+            return Err(invalid("server.read_chunk_size can not be 0"));
+        }
+        if server.events_capacity == 0 {
+            return Err(invalid("server.events_capacity can not be 0"));
+        }
+        if self.log.file_name.is_empty() {
+            return Err(invalid("log.file_name can not be empty"));
+        }
+        if self.log.rotation == LogRotation::Size && self.log.max_file_size == 0 {
+            return Err(invalid("log.max_file_size can not be 0 with size rotation"));
+        }
+        Ok(())
+    }
+
+    pub fn server_config(&self) -> NetServerConfig {
+        let server = &self.server;
+        NetServerConfig {
+            stream: self.stream.clone(),
+            max_outbound_bytes: server.max_outbound_bytes,
+            outbound_policy: server.outbound_policy,
+            require_handshake: server.require_handshake,
+            keepalive: (server.keepalive_interval_millis > 0).then(|| {
+                NetKeepaliveConfig {
+                    interval: Duration::from_millis(server.keepalive_interval_millis),
+                    max_missed: server.keepalive_max_missed,
+                }
+            }),
+            reassembly: NetReassemblerConfig {
+                max_message_size: server.max_message_size,
+                max_pending_messages: server.max_pending_messages,
+                max_pending_bytes: server.max_pending_bytes,
+                timeout: Duration::from_millis(server.reassembly_timeout_millis),
+            },
+            control: server.control,
+            control_admins: server.control_admins.clone(),
+            max_connections: server.max_connections,
+            read_chunk_size: server.read_chunk_size,
+            events_capacity: server.events_capacity,
+        }
+    }
+}
+
+fn invalid(message: impl ToString) -> NetConfigError {
+    NetConfigError::Invalid(message.to_string())
+}
+
+fn parse<T: FromStr>(
+    name: &str,
+    value: &str,
+) -> Result<T, NetConfigError> {
+    value.trim().parse().map_err(|_| env_error(name, value))
+}
+
+// comma separated, empty value for an empty list
+fn parse_list<T: FromStr>(
+    name: &str,
+    value: &str,
+) -> Result<Vec<T>, NetConfigError> {
+    value
+        .split(',')
+        .filter(|item| !item.trim().is_empty())
+        .map(|item| parse(name, item))
+        .collect()
+}
+
+// enums take the same names as in the file or on the command line
+fn parse_enum<T: DeserializeOwned>(
+    name: &str,
+    value: &str,
+) -> Result<T, NetConfigError> {
+    parse_name(value).map_err(|_| env_error(name, value))
+}
+
+// enum variant by the name it has in the file, case does not matter and
+// '-' can be used in place of '_'
+pub fn parse_name<T: DeserializeOwned>(name: &str) -> Result<T, value::Error> {
+    let name = name.trim().to_lowercase().replace('-', "_");
+    T::deserialize(name.into_deserializer())
+}
+
+fn env_error(
+    name: &str,
+    value: &str,
+) -> NetConfigError {
+    NetConfigError::Env {
+        name: name.to_string(),
+        value: value.to_string(),
+    }
+}
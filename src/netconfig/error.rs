@@ -0,0 +1,33 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use thiserror::Error;
+
+
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum NetConfigError {
+    #[error("can not read {path}: {message}")]
+    Read { path: String, message: String },
+
+    #[error("invalid configuration file {path}: {message}")]
+    Parse { path: String, message: String },
+
+    #[error("invalid value of {name}: {value}")]
+    Env { name: String, value: String },
+
+    #[error("invalid configuration: {0}")]
+    Invalid(String),
+}
@@ -0,0 +1,252 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::{fs, net::IpAddr, path::Path, time::Duration};
+
+use crate::{
+    logger::types::{LogRotation, LogTarget},
+    netconfig::{
+        error::NetConfigError,
+        types::{NetConfig, NetServerSection},
+    },
+    netfragment::types::NetReassemblerConfig,
+    netframe::types::NetFrameChecksum,
+    netserver::types::{NetOutboundPolicy, NetServerConfig},
+    netsession::types::NetKeepaliveConfig,
+    netstream::types::NetStreamConfig,
+};
+
+
+fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+// writes the file into the temp directory, unique per test
+fn write_config(
+    name: &str,
+    text: &str,
+) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("netstream-{}-{}", std::process::id(), name));
+    fs::write(&path, text).unwrap();
+    path
+}
+
+#[test]
+fn netconfig_ok_defaults() {
+    let config = NetConfig::default();
+    assert_eq!(config.validate(), Ok(()));
+    assert_eq!(config.stream, NetStreamConfig::default());
+    assert_eq!(config.server_config(), NetServerConfig::default());
+    // empty file is the defaults
+    let parsed: NetConfig = toml::from_str("").unwrap();
+    assert_eq!(parsed, config);
+}
+
+#[test]
+fn netconfig_ok_load_toml() {
+    let path = write_config(
+        "full.toml",
+        r#"
+            bind = "0.0.0.0:7000"
+
+            [stream]
+            max_frame_size = 100000
+            max_buffered_bytes = 200000
+            checksum = "xxhash32"
+            delimiter = 126
+
+            [server]
+            max_connections = 8
+            outbound_policy = "drop_oldest"
+            require_handshake = true
+            keepalive_interval_millis = 0
+            max_pending_bytes = 1048576
+            reassembly_timeout_millis = 5000
+        "#,
+    );
+    let config = NetConfig::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(config.bind, "0.0.0.0:7000".parse().unwrap());
+    assert_eq!(
+        config.stream,
+        NetStreamConfig {
+            max_frame_size: 100000,
+            max_buffered_bytes: 200000,
+            checksum: NetFrameChecksum::XxHash32,
+            delimiter: 0x7E,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        config.server,
+        NetServerSection {
+            max_connections: Some(8),
+            outbound_policy: NetOutboundPolicy::DropOldest,
+            require_handshake: true,
+            keepalive_interval_millis: 0,
+            max_pending_bytes: 1048576,
+            reassembly_timeout_millis: 5000,
+            ..Default::default()
+        }
+    );
+    assert_eq!(config.validate(), Ok(()));
+    let server = config.server_config();
+    assert_eq!(server.stream, config.stream);
+    assert_eq!(server.max_connections, Some(8));
+    assert_eq!(server.keepalive, None);
+    assert_eq!(
+        server.reassembly,
+        NetReassemblerConfig {
+            max_pending_bytes: 1048576,
+            timeout: Duration::from_millis(5000),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn netconfig_ok_load_json() {
+    let path = write_config(
+        "partial.json",
+        r#"{ "stream": { "checksum": "crc32" }, "server": { "keepalive_interval_millis": 1500 } }"#,
+    );
+    let config = NetConfig::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(config.stream.checksum, NetFrameChecksum::Crc32);
+    assert_eq!(
+        config.server_config().keepalive,
+        Some(NetKeepaliveConfig {
+            interval: Duration::from_millis(1500),
+            ..Default::default()
+        })
+    );
+    // the rest keeps the defaults
+    assert_eq!(config.bind, NetConfig::default().bind);
+}
+
+#[test]
+fn netconfig_failure_load() {
+    assert!(matches!(
+        NetConfig::load(Path::new("/nonexistent/netstream.toml")),
+        Err(NetConfigError::Read { .. })
+    ));
+    // typos are reported instead of silently ignored
+    let path = write_config("typo.toml", "[stream]\nmax_frame_sise = 10\n");
+    let result = NetConfig::load(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(result, Err(NetConfigError::Parse { .. })));
+    let path = write_config("type.json", r#"{ "bind": 10 }"#);
+    let result = NetConfig::load(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(result, Err(NetConfigError::Parse { .. })));
+}
+
+#[test]
+fn netconfig_ok_env() {
+    let mut config = NetConfig::default();
+    let ignored = config
+        .apply_env(vars(&[
+            ("NETSTREAM_BIND", "10.0.0.1:9000"),
+            ("NETSTREAM_STREAM_CHECKSUM", "crc32"),
+            ("NETSTREAM_STREAM_DELIMITER", "255"),
+            ("NETSTREAM_SERVER_MAX_CONNECTIONS", "2"),
+            ("NETSTREAM_SERVER_OUTBOUND_POLICY", "drop_oldest"),
+            ("NETSTREAM_SERVER_CONTROL", "true"),
+            ("NETSTREAM_SERVER_CONTROL_ADMINS", "127.0.0.1, ::1"),
+            ("NETSTREAM_LOG_TARGET", "both"),
+            ("NETSTREAM_LOG_ROTATION", "size"),
+            ("NETSTREAM_LOG_DIRECTORY", "/var/log/netstream"),
+            ("NETSTREAM_UNKNOWN", "ignored"),
+            ("PATH", "/bin"),
+        ]))
+        .unwrap();
+    assert_eq!(config.bind, "10.0.0.1:9000".parse().unwrap());
+    assert_eq!(config.stream.checksum, NetFrameChecksum::Crc32);
+    assert_eq!(config.stream.delimiter, 0xFF);
+    assert_eq!(config.server.max_connections, Some(2));
+    assert_eq!(config.server.outbound_policy, NetOutboundPolicy::DropOldest);
+    assert!(config.server.control);
+    assert_eq!(
+        config.server.control_admins,
+        vec![
+            "127.0.0.1".parse::<IpAddr>().unwrap(),
+            "::1".parse().unwrap()
+        ]
+    );
+    assert_eq!(config.log.target, LogTarget::Both);
+    assert_eq!(config.log.rotation, LogRotation::Size);
+    assert_eq!(config.log.directory, Path::new("/var/log/netstream"));
+    assert_eq!(ignored, vec!["NETSTREAM_UNKNOWN".to_string()]);
+}
+
+#[test]
+fn netconfig_failure_env() {
+    let mut config = NetConfig::default();
+    assert_eq!(
+        config.apply_env(vars(&[("NETSTREAM_STREAM_MAX_FRAME_SIZE", "big")])),
+        Err(NetConfigError::Env {
+            name: "NETSTREAM_STREAM_MAX_FRAME_SIZE".to_string(),
+            value: "big".to_string(),
+        })
+    );
+    assert!(
+        config
+            .apply_env(vars(&[("NETSTREAM_STREAM_CHECKSUM", "md5")]))
+            .is_err()
+    );
+    assert!(
+        config
+            .apply_env(vars(&[("NETSTREAM_STREAM_DELIMITER", "256")]))
+            .is_err()
+    );
+}
+
+#[test]
+fn netconfig_failure_validate() {
+    let cases: Vec<fn(&mut NetConfig)> = vec![
+        |config| config.stream.max_frame_size = 0,
+        |config| config.stream.max_frame_size = usize::MAX,
+        |config| config.stream.max_buffered_bytes = 10,
+        |config| config.stream.max_queued_frames = 0,
+        |config| config.server.max_connections = Some(0),
+        |config| config.server.max_outbound_bytes = 0,
+        |config| config.server.keepalive_max_missed = 0,
+        |config| config.server.max_message_size = 0,
+        |config| config.server.max_pending_messages = 0,
+        |config| config.server.max_pending_bytes = 0,
+        |config| config.server.reassembly_timeout_millis = 0,
+        |config| config.server.read_chunk_size = 0,
+        |config| config.server.events_capacity = 0,
+        |config| config.log.file_name.clear(),
+        |config| {
+            config.log.rotation = LogRotation::Size;
+            config.log.max_file_size = 0;
+        },
+    ];
+    for case in cases {
+        let mut config = NetConfig::default();
+        case(&mut config);
+        assert!(matches!(config.validate(), Err(NetConfigError::Invalid(_))));
+    }
+    // missed pongs do not matter with pings disabled
+    let mut config = NetConfig::default();
+    config.server.keepalive_interval_millis = 0;
+    config.server.keepalive_max_missed = 0;
+    assert_eq!(config.validate(), Ok(()));
+}
@@ -0,0 +1,96 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::net::{IpAddr, SocketAddr};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    logger::types::LogConfig,
+    netserver::types::NetOutboundPolicy,
+    netstream::types::NetStreamConfig,
+};
+
+
+// configuration file, every key is optional and defaults to the
+// matching NETSTREAM_*/NETSERVER_*/NETSESSION_* constant:
+//
+//   bind = "127.0.0.1:6669"
+//
+//   [stream]
+//   max_buffered_bytes = 196605
+//   max_queued_frames = 256
+//   max_frame_size = 65535
+//   checksum = "none"                 # none, crc32, xxhash32
+//   delimiter = 0
+//   mode = "strict"                   # strict, resync
+//
+//   [server]
+//   max_connections = 64              # unlimited if missing
+//   max_outbound_bytes = 1048576
+//   outbound_policy = "block"         # block, drop_oldest, disconnect
+//   require_handshake = false
+//   control = false
+//   control_admins = ["127.0.0.1"]    # admin control requests, nobody if empty
+//   keepalive_interval_millis = 30000 # 0 disables pings
+//   keepalive_max_missed = 3
+//   max_message_size = 16777216       # MultiMessage reassembly limits,
+//   max_pending_messages = 16         # per connection
+//   max_pending_bytes = 16777216
+//   reassembly_timeout_millis = 30000
+//   read_chunk_size = 4096
+//   events_capacity = 128
+//
+//   [log]
+//   target = "stdout"                 # stdout, file, both
+//   format = "plain"                  # plain, json
+//   ansi = true
+//   directory = "logs"
+//   file_name = "netstream.log"
+//   rotation = "daily"                # never, hourly, daily, size
+//   max_file_size = 10485760          # size rotation only
+//   max_files = 7                     # 0 keeps all of them
+//
+// JSON files (.json extension) use the same layout. Layers, the last one
+// wins: defaults, file, NETSTREAM_* environment variables, command line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetConfig {
+    pub bind: SocketAddr,
+    pub stream: NetStreamConfig,
+    pub server: NetServerSection,
+    pub log: LogConfig,
+}
+
+// [server] section, turned into NetServerConfig by server_config()
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetServerSection {
+    pub max_connections: Option<usize>,
+    pub max_outbound_bytes: usize,
+    pub outbound_policy: NetOutboundPolicy,
+    pub require_handshake: bool,
+    pub control: bool,
+    pub control_admins: Vec<IpAddr>,
+    pub keepalive_interval_millis: u64,
+    pub keepalive_max_missed: u32,
+    pub max_message_size: usize,
+    pub max_pending_messages: usize,
+    pub max_pending_bytes: usize,
+    pub reassembly_timeout_millis: u64,
+    pub read_chunk_size: usize,
+    pub events_capacity: usize,
+}
@@ -0,0 +1,65 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use crate::{
+    netcontrol::{error::NetControlError, types::NetControl},
+    netframe::types::{NetFrame, NetFrameTag},
+};
+
+impl NetControl {
+    pub fn from_frame(frame: &NetFrame) -> Result<Self, NetControlError> {
+        if frame.kind() != NetFrameTag::Control {
+            return Err(NetControlError::NotControl);
+        }
+        serde_json::from_slice(&frame.data).map_err(|err| NetControlError::Invalid(err.to_string()))
+    }
+
+    pub fn to_frame(&self) -> NetFrame {
+        // plain enum of plain data, serializing it can not fail
+        let data = serde_json::to_vec(self).unwrap_or_default();
+        NetFrame::with_kind(NetFrameTag::Control, data)
+    }
+
+    // requests acting on the whole server or on other connections than
+    // the requesting one
+    pub fn admin(
+        &self,
+        id: usize,
+    ) -> bool {
+        match self {
+            NetControl::QueryStats {
+                id: Some(other),
+            } => *other != id,
+            NetControl::SetLogLevel {
+                ..
+            }
+            | NetControl::Pause {
+                ..
+            }
+            | NetControl::Resume {
+                ..
+            }
+            | NetControl::ListConnections => true,
+            _ => false,
+        }
+    }
+
+    pub fn error(message: impl ToString) -> Self {
+        NetControl::Error {
+            message: message.to_string(),
+        }
+    }
+}
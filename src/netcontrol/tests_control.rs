@@ -0,0 +1,123 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use bytes::Bytes;
+
+use crate::{
+    netcontrol::{
+        error::NetControlError,
+        types::{NetControl, NetControlOption},
+    },
+    netframe::types::{NetFrame, NetFrameTag},
+    netserver::types::NetOutboundPolicy,
+};
+
+
+#[test]
+fn control_ok_roundtrip() {
+    let messages = vec![
+        NetControl::SetOption(NetControlOption::MaxOutboundBytes(4096)),
+        NetControl::SetOption(NetControlOption::OutboundPolicy(
+            NetOutboundPolicy::DropOldest,
+        )),
+        NetControl::QueryStats {
+            id: Some(3),
+        },
+        NetControl::SetLogLevel {
+            level: "debug".into(),
+        },
+        NetControl::Pause {
+            id: 1,
+        },
+        NetControl::ListConnections,
+        NetControl::Ok,
+        NetControl::error("nope"),
+    ];
+    for message in messages {
+        let frame = message.to_frame();
+        assert_eq!(frame.kind(), NetFrameTag::Control);
+        assert_eq!(NetControl::from_frame(&frame), Ok(message));
+    }
+}
+
+#[test]
+fn control_ok_admin_requests() {
+    let own = [
+        NetControl::SetOption(NetControlOption::MaxOutboundBytes(4096)),
+        NetControl::QueryStats {
+            id: None,
+        },
+        NetControl::QueryStats {
+            id: Some(1),
+        },
+    ];
+    for request in own {
+        assert!(!request.admin(1), "{:?}", request);
+    }
+    let admin = [
+        NetControl::QueryStats {
+            id: Some(2),
+        },
+        NetControl::SetLogLevel {
+            level: "debug".into(),
+        },
+        NetControl::Pause {
+            id: 2,
+        },
+        NetControl::Resume {
+            id: 2,
+        },
+        NetControl::ListConnections,
+    ];
+    for request in admin {
+        assert!(request.admin(1), "{:?}", request);
+    }
+}
+
+#[test]
+fn control_ok_wire_format() {
+    let frame = NetControl::SetOption(NetControlOption::KeepaliveIntervalMillis(500)).to_frame();
+    assert_eq!(
+        frame.data,
+        Bytes::from_static(
+            br#"{"type":"set_option","option":"keepalive_interval_millis","value":500}"#
+        )
+    );
+    let frame = NetFrame::with_kind(
+        NetFrameTag::Control,
+        Bytes::from_static(br#"{"type":"query_stats","id":null}"#),
+    );
+    assert_eq!(
+        NetControl::from_frame(&frame),
+        Ok(NetControl::QueryStats {
+            id: None
+        })
+    );
+}
+
+#[test]
+fn control_failure_invalid() {
+    let frame = NetFrame::with_kind(NetFrameTag::Control, Bytes::from_static(b"{}"));
+    assert!(matches!(
+        NetControl::from_frame(&frame),
+        Err(NetControlError::Invalid(_))
+    ));
+    let frame = NetFrame::with_kind(NetFrameTag::SingleMessage, Bytes::from_static(b"{}"));
+    assert_eq!(
+        NetControl::from_frame(&frame),
+        Err(NetControlError::NotControl)
+    );
+}
@@ -0,0 +1,90 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// Control frames carry a json encoded NetControl, tagged by "type":
+//
+//   {"type":"query_stats","id":null}
+//   {"type":"set_option","option":"max_outbound_bytes","value":65536}
+//   {"type":"pause","id":3}
+//
+// Servers with the control channel enabled handle them before the
+// connection handler and answer every request with a Control frame:
+// stats, connections, ok or error. Requests without an id apply to the
+// connection they came in on. Admin requests, see NetControl::admin(),
+// are only answered for peers on the server's control_admins list, and
+// SetOption can not raise the limits the server was configured with.
+
+use std::net::SocketAddr;
+
+use serde_derive::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::netserver::types::NetOutboundPolicy;
+
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NetControl {
+    // |===| requests
+    // changes an option of the requesting connection
+    SetOption(NetControlOption),
+    QueryStats { id: Option<usize> },
+    // tracing filter directives, i.e. "info" or "netstream=debug"
+    SetLogLevel { level: String },
+    // stops reading from the connection, frames it sends are not delivered
+    // until resumed. Connection can not pause itself.
+    Pause { id: usize },
+    Resume { id: usize },
+    ListConnections,
+
+    // |===| replies
+    Stats(NetConnectionStats),
+    Connections { connections: Vec<NetConnectionInfo> },
+    Ok,
+    Error { message: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "option", content = "value", rename_all = "snake_case")]
+pub enum NetControlOption {
+    MaxOutboundBytes(usize),
+    OutboundPolicy(NetOutboundPolicy),
+    // 0 disables keepalive pings
+    KeepaliveIntervalMillis(u64),
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetConnectionStats {
+    pub id: usize,
+    pub frames_received: u64,
+    pub bytes_received: u64,
+    pub frames_sent: u64,
+    pub bytes_sent: u64,
+    // outbound frames dropped by the DropOldest policy
+    pub frames_dropped: u64,
+    // inbound bytes skipped while resynchronizing after corruption
+    pub bytes_skipped: u64,
+    // smoothed round trip time, None until the first Pong
+    pub rtt_micros: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetConnectionInfo {
+    pub id: usize,
+    pub address: SocketAddr,
+    pub session_id: Uuid,
+    pub paused: bool,
+}
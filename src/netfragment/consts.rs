@@ -0,0 +1,30 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use crate::netframe::consts::NETFRAME_MAX_DATA_SIZE;
+
+// message id (32bit), fragment index (16bit), flags (8bit)
+pub const NETFRAGMENT_HEADER_SIZE_BYTES: usize = 7;
+pub const NETFRAGMENT_FLAG_FINAL: u8 = 0x01;
+// default fragment data size, so every fragment fits the short frame header
+pub const NETFRAGMENT_MAX_DATA_SIZE: usize = NETFRAME_MAX_DATA_SIZE - NETFRAGMENT_HEADER_SIZE_BYTES;
+pub const NETFRAGMENT_MAX_COUNT: usize = u16::MAX as usize + 1;
+// reassembler defaults, pending bytes are counted across all the
+// messages of one connection
+pub const NETFRAGMENT_MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+pub const NETFRAGMENT_MAX_PENDING_MESSAGES: usize = 16;
+pub const NETFRAGMENT_MAX_PENDING_BYTES: usize = 16 * 1024 * 1024;
+pub const NETFRAGMENT_TIMEOUT_SECS: u64 = 30;
@@ -0,0 +1,267 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::time::{Duration, Instant};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use crate::{
+    netfragment::{
+        consts::*,
+        error::NetFragmentError,
+        types::{
+            NetFragmentHeader,
+            NetFragmenter,
+            NetPartialMessage,
+            NetReassembler,
+            NetReassemblerConfig,
+        },
+    },
+    netframe::{
+        consts::NETFRAME_EXTENDED_MAX_DATA_SIZE,
+        types::{NetFrame, NetFrameTag},
+    },
+};
+
+impl NetFragmentHeader {
+    pub fn read(data: &[u8]) -> Result<Self, NetFragmentError> {
+        if data.len() < NETFRAGMENT_HEADER_SIZE_BYTES {
+            return Err(NetFragmentError::TooLittleData);
+        }
+        Ok(Self {
+            message_id: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+            index: u16::from_be_bytes([data[4], data[5]]),
+            last: data[6] & NETFRAGMENT_FLAG_FINAL != 0,
+        })
+    }
+
+    pub fn write(
+        &self,
+        buffer: &mut BytesMut,
+    ) {
+        buffer.put_u32(self.message_id);
+        buffer.put_u16(self.index);
+        buffer.put_u8(if self.last {
+            NETFRAGMENT_FLAG_FINAL
+        } else {
+            0x00
+        });
+    }
+}
+
+impl NetFragmenter {
+    pub fn new(fragment_size: usize) -> Self {
+        Self {
+            fragment_size: fragment_size.clamp(
+                1,
+                NETFRAME_EXTENDED_MAX_DATA_SIZE - NETFRAGMENT_HEADER_SIZE_BYTES,
+            ),
+            next_message_id: 0,
+        }
+    }
+
+    // splits the message into MultiMessage frames, every call uses a new
+    // message id. Empty message is sent as a single, empty, final fragment.
+    pub fn split(
+        &mut self,
+        message: &[u8],
+    ) -> Result<Vec<NetFrame>, NetFragmentError> {
+        let count = message.len().div_ceil(self.fragment_size).max(1);
+        if count > NETFRAGMENT_MAX_COUNT {
+            return Err(NetFragmentError::TooManyFragments);
+        }
+
+        let message_id = self.next_message_id;
+        self.next_message_id = self.next_message_id.wrapping_add(1);
+
+        let frames = (0..count)
+            .map(|index| {
+                let start = index * self.fragment_size;
+                let end = message.len().min(start + self.fragment_size);
+                let mut data = BytesMut::with_capacity(NETFRAGMENT_HEADER_SIZE_BYTES + end - start);
+                NetFragmentHeader {
+                    message_id,
+                    index: index as u16,
+                    last: index + 1 == count,
+                }
+                .write(&mut data);
+                data.extend_from_slice(&message[start..end]);
+                NetFrame::with_kind(NetFrameTag::MultiMessage, data.freeze())
+            })
+            .collect();
+
+        Ok(frames)
+    }
+}
+
+impl Default for NetFragmenter {
+    fn default() -> Self {
+        Self::new(NETFRAGMENT_MAX_DATA_SIZE)
+    }
+}
+
+impl Default for NetReassemblerConfig {
+    fn default() -> Self {
+        Self {
+            max_message_size: NETFRAGMENT_MAX_MESSAGE_SIZE,
+            max_pending_messages: NETFRAGMENT_MAX_PENDING_MESSAGES,
+            max_pending_bytes: NETFRAGMENT_MAX_PENDING_BYTES,
+            timeout: Duration::from_secs(NETFRAGMENT_TIMEOUT_SECS),
+        }
+    }
+}
+
+impl NetReassembler {
+    pub fn new(
+        max_message_size: usize,
+        timeout: Duration,
+    ) -> Self {
+        Self::with_config(NetReassemblerConfig {
+            max_message_size,
+            timeout,
+            ..Default::default()
+        })
+    }
+
+    pub fn with_config(config: NetReassemblerConfig) -> Self {
+        Self {
+            config,
+            pending: Default::default(),
+            pending_bytes: 0,
+        }
+    }
+
+    // takes the next fragment, returns the message once its final
+    // fragment arrives. On error the message the fragment belongs to
+    // is dropped, other messages are not affected.
+    pub fn push(
+        &mut self,
+        frame: &NetFrame,
+    ) -> Result<Option<Bytes>, NetFragmentError> {
+        self.push_at(frame, Instant::now())
+    }
+
+    pub fn push_at(
+        &mut self,
+        frame: &NetFrame,
+        now: Instant,
+    ) -> Result<Option<Bytes>, NetFragmentError> {
+        if frame.kind() != NetFrameTag::MultiMessage {
+            return Err(NetFragmentError::NotMultiMessage);
+        }
+        let header = NetFragmentHeader::read(&frame.data)?;
+        let mut data = frame.data.clone();
+        data.advance(NETFRAGMENT_HEADER_SIZE_BYTES);
+
+        self.expire(now);
+
+        let message_id = header.message_id;
+        let mut partial = match self.remove(message_id) {
+            Some(partial) if partial.next_index == header.index => partial,
+            Some(_) => return Err(NetFragmentError::OutOfOrder(message_id)),
+            None if header.index != 0 => return Err(NetFragmentError::OutOfOrder(message_id)),
+            // single fragment message, no need to copy it
+            None if header.last => {
+                if data.len() > self.config.max_message_size {
+                    return Err(NetFragmentError::MessageTooLong(message_id));
+                }
+                return Ok(Some(data));
+            }
+            None => {
+                if self.pending.len() >= self.config.max_pending_messages {
+                    return Err(NetFragmentError::TooManyMessages);
+                }
+                NetPartialMessage {
+                    data: BytesMut::new(),
+                    next_index: 0,
+                    started: now,
+                }
+            }
+        };
+
+        if partial.data.len() + data.len() > self.config.max_message_size {
+            return Err(NetFragmentError::MessageTooLong(message_id));
+        }
+
+        if header.last {
+            partial.data.extend_from_slice(&data);
+            return Ok(Some(partial.data.freeze()));
+        }
+        // the whole message counts, its earlier fragments were taken
+        // out of pending_bytes with it
+        if self.pending_bytes + partial.data.len() + data.len() > self.config.max_pending_bytes {
+            return Err(NetFragmentError::TooMuchPendingData(message_id));
+        }
+        partial.data.extend_from_slice(&data);
+        // index would overflow with the next fragment
+        partial.next_index = match header.index.checked_add(1) {
+            Some(next_index) => next_index,
+            None => return Err(NetFragmentError::TooManyFragments),
+        };
+        self.pending_bytes += partial.data.len();
+        self.pending.insert(message_id, partial);
+
+        Ok(None)
+    }
+
+    // drops messages that were not completed within the timeout,
+    // returns their ids
+    pub fn expire(
+        &mut self,
+        now: Instant,
+    ) -> Vec<u32> {
+        let timeout = self.config.timeout;
+        let expired: Vec<u32> = self
+            .pending
+            .iter()
+            .filter(|(_, partial)| now.saturating_duration_since(partial.started) >= timeout)
+            .map(|(message_id, _)| *message_id)
+            .collect();
+        for message_id in &expired {
+            self.remove(*message_id);
+        }
+        expired
+    }
+
+    // when the oldest incomplete message expires, None if there is none
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.pending
+            .values()
+            .map(|partial| partial.started + self.config.timeout)
+            .min()
+    }
+
+    // drops every message being reassembled
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.pending_bytes = 0;
+    }
+
+    fn remove(
+        &mut self,
+        message_id: u32,
+    ) -> Option<NetPartialMessage> {
+        let partial = self.pending.remove(&message_id)?;
+        self.pending_bytes -= partial.data.len();
+        Some(partial)
+    }
+}
+
+impl Default for NetReassembler {
+    fn default() -> Self {
+        Self::with_config(Default::default())
+    }
+}
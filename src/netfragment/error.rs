@@ -0,0 +1,46 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use thiserror::Error;
+
+
+#[derive(Error, Debug, PartialEq, Clone, Default)]
+pub enum NetFragmentError {
+    #[default]
+    #[error("Undefined fragment error")]
+    Undefined,
+
+    #[error("frame is not tagged as MultiMessage")]
+    NotMultiMessage,
+
+    #[error("Not enough data to read the fragment header")]
+    TooLittleData,
+
+    #[error("message {0} exceeds the maximum message size")]
+    MessageTooLong(u32),
+
+    #[error("message needs more fragments than the index can address")]
+    TooManyFragments,
+
+    #[error("too many messages are being reassembled at once")]
+    TooManyMessages,
+
+    #[error("message {0} does not fit the data pending reassembly")]
+    TooMuchPendingData(u32),
+
+    #[error("fragment of message {0} arrived out of order")]
+    OutOfOrder(u32),
+}
@@ -0,0 +1,200 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+
+use crate::{
+    netfragment::{
+        error::NetFragmentError,
+        types::{NetFragmentHeader, NetFragmenter, NetReassembler, NetReassemblerConfig},
+    },
+    netframe::types::{NetFrame, NetFrameTag},
+    netstream::types::{FramingStream, NetStream},
+};
+
+
+#[test]
+fn fragment_ok_split_layout() {
+    let mut fragmenter = NetFragmenter::new(4);
+    let frames = fragmenter.split(b"0123456789").unwrap();
+    assert_eq!(frames.len(), 3);
+    for (index, frame) in frames.iter().enumerate() {
+        assert_eq!(frame.kind(), NetFrameTag::MultiMessage);
+        assert_eq!(
+            NetFragmentHeader::read(&frame.data),
+            Ok(NetFragmentHeader {
+                message_id: 0,
+                index: index as u16,
+                last: index == 2,
+            })
+        );
+    }
+    // message id, index, flags, data
+    assert_eq!(
+        frames[2].data,
+        Bytes::from_static(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x01, b'8', b'9'])
+    );
+    // next message gets a new id
+    let frames = fragmenter.split(b"").unwrap();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(
+        NetFragmentHeader::read(&frames[0].data).unwrap().message_id,
+        1
+    );
+}
+
+#[test]
+fn fragment_ok_round_trip_through_netstream() {
+    let message: Vec<u8> = (0..100_000u32).map(|i| i as u8).collect();
+    let mut fragmenter = NetFragmenter::default();
+    let mut reassembler = NetReassembler::default();
+    let mut stream = NetStream::new();
+    let mut result = None;
+    for frame in fragmenter.split(&message).unwrap() {
+        stream.write(&frame.encode().unwrap()).unwrap();
+        let frame = stream.next().unwrap();
+        assert!(result.is_none());
+        result = reassembler.push(&frame).unwrap();
+    }
+    assert_eq!(result, Some(Bytes::from(message)));
+    assert!(reassembler.pending.is_empty());
+}
+
+#[test]
+fn fragment_ok_interleaved_messages() {
+    let mut fragmenter = NetFragmenter::new(2);
+    let mut reassembler = NetReassembler::default();
+    let first = fragmenter.split(b"aabbcc").unwrap();
+    let second = fragmenter.split(b"xxyy").unwrap();
+    assert_eq!(reassembler.push(&first[0]), Ok(None));
+    assert_eq!(reassembler.push(&second[0]), Ok(None));
+    assert_eq!(reassembler.push(&first[1]), Ok(None));
+    assert_eq!(
+        reassembler.push(&second[1]),
+        Ok(Some(Bytes::from_static(b"xxyy")))
+    );
+    assert_eq!(
+        reassembler.push(&first[2]),
+        Ok(Some(Bytes::from_static(b"aabbcc")))
+    );
+}
+
+#[test]
+fn fragment_failure_message_too_long() {
+    let mut fragmenter = NetFragmenter::new(4);
+    let mut reassembler = NetReassembler::new(6, Duration::from_secs(1));
+    let frames = fragmenter.split(b"0123456789").unwrap();
+    assert_eq!(reassembler.push(&frames[0]), Ok(None));
+    assert_eq!(
+        reassembler.push(&frames[1]),
+        Err(NetFragmentError::MessageTooLong(0))
+    );
+    // message is dropped, rest of it is out of order now
+    assert!(reassembler.pending.is_empty());
+    assert_eq!(
+        reassembler.push(&frames[2]),
+        Err(NetFragmentError::OutOfOrder(0))
+    );
+}
+
+#[test]
+fn fragment_failure_out_of_order_and_not_multimessage() {
+    let mut fragmenter = NetFragmenter::new(1);
+    let mut reassembler = NetReassembler::default();
+    let frames = fragmenter.split(b"abc").unwrap();
+    assert_eq!(reassembler.push(&frames[0]), Ok(None));
+    assert_eq!(
+        reassembler.push(&frames[2]),
+        Err(NetFragmentError::OutOfOrder(0))
+    );
+    assert_eq!(
+        reassembler.push(&NetFrame::with_kind(NetFrameTag::SingleMessage, vec![])),
+        Err(NetFragmentError::NotMultiMessage)
+    );
+    assert_eq!(
+        reassembler.push(&NetFrame::with_kind(NetFrameTag::MultiMessage, vec![0x00])),
+        Err(NetFragmentError::TooLittleData)
+    );
+}
+
+#[test]
+fn fragment_ok_reassembly_timeout() {
+    let mut fragmenter = NetFragmenter::new(2);
+    let mut reassembler = NetReassembler::new(1024, Duration::from_secs(5));
+    let slow = fragmenter.split(b"aabb").unwrap();
+    let fast = fragmenter.split(b"xxyy").unwrap();
+    let start = Instant::now();
+    assert_eq!(reassembler.push_at(&slow[0], start), Ok(None));
+    assert_eq!(
+        reassembler.push_at(&fast[0], start + Duration::from_secs(3)),
+        Ok(None)
+    );
+    // slow message timed out, fast one is still in time
+    assert_eq!(reassembler.expire(start + Duration::from_secs(6)), vec![0]);
+    assert_eq!(
+        reassembler.push_at(&slow[1], start + Duration::from_secs(6)),
+        Err(NetFragmentError::OutOfOrder(0))
+    );
+    assert_eq!(
+        reassembler.push_at(&fast[1], start + Duration::from_secs(7)),
+        Ok(Some(Bytes::from_static(b"xxyy")))
+    );
+}
+
+#[test]
+fn fragment_failure_too_many_messages() {
+    let mut fragmenter = NetFragmenter::new(1);
+    let mut reassembler = NetReassembler::with_config(NetReassemblerConfig {
+        max_pending_messages: 2,
+        ..Default::default()
+    });
+    let messages: Vec<Vec<NetFrame>> = (0..3).map(|_| fragmenter.split(b"ab").unwrap()).collect();
+    assert_eq!(reassembler.push(&messages[0][0]), Ok(None));
+    assert_eq!(reassembler.push(&messages[1][0]), Ok(None));
+    assert_eq!(
+        reassembler.push(&messages[2][0]),
+        Err(NetFragmentError::TooManyMessages)
+    );
+}
+
+#[test]
+fn fragment_failure_too_much_pending_data() {
+    let mut fragmenter = NetFragmenter::new(4);
+    let mut reassembler = NetReassembler::with_config(NetReassemblerConfig {
+        max_pending_bytes: 10,
+        ..Default::default()
+    });
+    let first = fragmenter.split(b"aaaabbbbcc").unwrap();
+    let second = fragmenter.split(b"ddddeeee").unwrap();
+    assert_eq!(reassembler.push(&first[0]), Ok(None));
+    assert_eq!(reassembler.push(&second[0]), Ok(None));
+    assert_eq!(reassembler.pending_bytes, 8);
+    // both messages together would hold 12 bytes
+    assert_eq!(
+        reassembler.push(&first[1]),
+        Err(NetFragmentError::TooMuchPendingData(0))
+    );
+    assert_eq!(reassembler.pending_bytes, 4);
+    // the final fragment completes the message, it is not held back
+    assert_eq!(
+        reassembler.push(&second[1]),
+        Ok(Some(Bytes::from_static(b"ddddeeee")))
+    );
+    assert_eq!(reassembler.pending_bytes, 0);
+    assert_eq!(reassembler.next_expiry(), None);
+}
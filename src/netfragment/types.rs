@@ -0,0 +1,77 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// MultiMessage frames carry a fragment header in front of the data:
+// ┌────────────┬────────┬───────┬────────────┐
+// │   32bit    │ 16bit  │ 8bit  │            │
+// │            │        │       │            │
+// │ message id │ index  │ flags │    data    │
+// └────────────┴────────┴───────┴────────────┘
+//
+// * message id is picked by the sender, unique among messages in flight
+// * index starts at 0 and grows by one with every fragment of the message
+// * flags, 0x01 marks the final fragment
+// * all fields are in network byte order
+//
+// Fragments of one message have to arrive in order, but fragments of
+// different messages can be interleaved on one connection.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use bytes::BytesMut;
+
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NetFragmentHeader {
+    pub message_id: u32,
+    pub index: u16,
+    pub last: bool,
+}
+
+// splits messages into MultiMessage frames
+#[derive(Debug, Clone)]
+pub struct NetFragmenter {
+    // data carried by a single fragment, fragment header not included
+    pub fragment_size: usize,
+    pub next_message_id: u32,
+}
+
+#[derive(Debug)]
+pub struct NetPartialMessage {
+    pub data: BytesMut,
+    pub next_index: u16,
+    pub started: Instant,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetReassemblerConfig {
+    pub max_message_size: usize,
+    pub max_pending_messages: usize,
+    // data held by all the incomplete messages together
+    pub max_pending_bytes: usize,
+    pub timeout: Duration,
+}
+
+// collects MultiMessage frames into whole messages
+#[derive(Debug)]
+pub struct NetReassembler {
+    pub config: NetReassemblerConfig,
+    pub pending: HashMap<u32, NetPartialMessage>,
+    pub pending_bytes: usize,
+}
@@ -16,3 +16,16 @@ limitations under the License.
 
 pub const NETFRAME_DELIMITER: u8 = 0x00;
 pub const NETFRAME_HEADER_SIZE_BYTES: usize = 4;
+// largest data that fits the 16bit length field
+pub const NETFRAME_MAX_DATA_SIZE: usize = u16::MAX as usize;
+// short length of the extended header, only for peers that opted in by
+// raising their max frame size above NETFRAME_MAX_DATA_SIZE
+pub const NETFRAME_EXTENDED_LENGTH_MARKER: u16 = u16::MAX;
+pub const NETFRAME_EXTENDED_HEADER_SIZE_BYTES: usize = 8;
+pub const NETFRAME_EXTENDED_MAX_DATA_SIZE: usize = u32::MAX as usize;
+// tag values reserved for application defined tags, see
+// NetFrameTag::Application
+pub const NETFRAME_TAG_APPLICATION_FIRST: u8 = 0x80;
+pub const NETFRAME_TAG_APPLICATION_LAST: u8 = 0xFE;
+// checksum trailer, present only if the checksum is enabled
+pub const NETFRAME_CHECKSUM_SIZE_BYTES: usize = 4;
@@ -14,33 +14,344 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
-// todo:esavier configurability?
-use super::consts::NETFRAME_DELIMITER;
+use std::io::Write;
+
+use bytes::{Buf, Bytes, BytesMut};
+
 use crate::netframe::{
+    consts::{
+        NETFRAME_CHECKSUM_SIZE_BYTES,
+        NETFRAME_DELIMITER,
+        NETFRAME_EXTENDED_HEADER_SIZE_BYTES,
+        NETFRAME_EXTENDED_LENGTH_MARKER,
+        NETFRAME_EXTENDED_MAX_DATA_SIZE,
+        NETFRAME_HEADER_SIZE_BYTES,
+        NETFRAME_MAX_DATA_SIZE,
+    },
     error::NetFrameError,
-    types::{NetFrame, NetFrameMetadata},
+    types::{NetFrame, NetFrameChecksum, NetFrameMetadata, NetFrameTag},
 };
 impl NetFrame {
     pub fn new(
         tag: u8,
-        data: Vec<u8>,
+        data: impl Into<Bytes>,
     ) -> Self {
         Self {
             tag,
-            data,
+            data: data.into(),
         }
     }
 
-    pub fn get_metadata(buffer: &Vec<u8>) -> Result<NetFrameMetadata, NetFrameError> {
+    pub fn with_kind(
+        kind: NetFrameTag,
+        data: impl Into<Bytes>,
+    ) -> Self {
+        Self::new(kind.into(), data)
+    }
+
+    pub fn kind(&self) -> NetFrameTag {
+        NetFrameTag::from(self.tag)
+    }
+
+    // short header only, 0xFFFF is a regular length
+    pub fn get_metadata(buffer: &[u8]) -> Result<NetFrameMetadata, NetFrameError> {
+        NetFrame::get_metadata_delimited(buffer, NETFRAME_MAX_DATA_SIZE, NETFRAME_DELIMITER)
+    }
+
+    // same as get_metadata(), for peers using another delimiter byte or
+    // frames above NETFRAME_MAX_DATA_SIZE, see NetFrame::extended()
+    pub fn get_metadata_delimited(
+        buffer: &[u8],
+        max_size: usize,
+        delimiter: u8,
+    ) -> Result<NetFrameMetadata, NetFrameError> {
         if buffer.len() < 4 {
             return Err(NetFrameError::TooLittleData);
         };
-        if buffer[0] != NETFRAME_DELIMITER {
+        if buffer[0] != delimiter {
             return Err(NetFrameError::DelimiterMismatch);
         };
+        let size = ((buffer[2] as u16) << 8) | (buffer[3] as u16);
+        if !NetFrame::extended(max_size) || size != NETFRAME_EXTENDED_LENGTH_MARKER {
+            return Ok(NetFrameMetadata {
+                tag: buffer[1],
+                size: size as u32,
+                extended: false,
+            });
+        }
+        // extended header, real length follows the marker
+        if buffer.len() < NETFRAME_EXTENDED_HEADER_SIZE_BYTES {
+            return Err(NetFrameError::TooLittleData);
+        };
+        let size = u32::from_be_bytes([buffer[4], buffer[5], buffer[6], buffer[7]]);
+        if size < NETFRAME_EXTENDED_LENGTH_MARKER as u32 {
+            return Err(NetFrameError::InvalidLength);
+        }
         Ok(NetFrameMetadata {
             tag: buffer[1],
-            size: ((buffer[2] as u16) << 8) | (buffer[3] as u16),
+            size,
+            extended: true,
         })
     }
+
+    // extended headers are used only by peers configured for frames that
+    // do not fit the short one, both sides have to agree on it. Then 0xFFFF
+    // in the short length field is the marker and not a length.
+    pub fn extended(max_size: usize) -> bool {
+        max_size > NETFRAME_MAX_DATA_SIZE
+    }
+
+    // splits the next complete frame off the front of the buffer without
+    // copying the data. Returns None if the frame is not complete yet,
+    // frames declaring more than max_size bytes of data are rejected.
+    // If checksum is enabled the trailer is verified and dropped.
+    pub fn decode(
+        buffer: &mut BytesMut,
+        max_size: usize,
+        checksum: NetFrameChecksum,
+    ) -> Result<Option<NetFrame>, NetFrameError> {
+        NetFrame::decode_delimited(buffer, max_size, checksum, NETFRAME_DELIMITER)
+    }
+
+    pub fn decode_delimited(
+        buffer: &mut BytesMut,
+        max_size: usize,
+        checksum: NetFrameChecksum,
+        delimiter: u8,
+    ) -> Result<Option<NetFrame>, NetFrameError> {
+        let metadata = match NetFrame::get_metadata_delimited(buffer, max_size, delimiter) {
+            Ok(metadata) => metadata,
+            Err(NetFrameError::TooLittleData) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        if metadata.size as usize > max_size {
+            return Err(NetFrameError::MessageTooLong);
+        }
+
+        let end = metadata.header_size() + metadata.size as usize;
+        if buffer.len() < end + checksum.size() {
+            return Ok(None);
+        }
+
+        if checksum != NetFrameChecksum::None {
+            let trailer = &buffer[end..end + NETFRAME_CHECKSUM_SIZE_BYTES];
+            let expected = u32::from_be_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+            if checksum.compute(&[&buffer[..end]]) != expected {
+                return Err(NetFrameError::ChecksumMismatch);
+            }
+        }
+
+        let mut data = buffer.split_to(end);
+        data.advance(metadata.header_size());
+        buffer.advance(checksum.size());
+
+        Ok(Some(NetFrame {
+            tag: metadata.tag,
+            data: data.freeze(),
+        }))
+    }
+
+    // size of the frame on the wire, header included
+    pub fn encoded_len(&self) -> usize {
+        self.encoded_len_with(NetFrameChecksum::None)
+    }
+
+    pub fn encoded_len_with(
+        &self,
+        checksum: NetFrameChecksum,
+    ) -> usize {
+        self.encoded_len_delimited(NETFRAME_MAX_DATA_SIZE, checksum)
+    }
+
+    // for a peer accepting up to max_size bytes of data
+    pub fn encoded_len_delimited(
+        &self,
+        max_size: usize,
+        checksum: NetFrameChecksum,
+    ) -> usize {
+        self.header_size(NetFrame::extended(max_size)) + self.data.len() + checksum.size()
+    }
+
+    // encodes the frame into a freshly allocated buffer,
+    // short header only, so the data is limited to NETFRAME_MAX_DATA_SIZE
+    pub fn encode(&self) -> Result<Vec<u8>, NetFrameError> {
+        self.encode_with(NetFrameChecksum::None)
+    }
+
+    pub fn encode_with(
+        &self,
+        checksum: NetFrameChecksum,
+    ) -> Result<Vec<u8>, NetFrameError> {
+        self.encode_delimited(NETFRAME_MAX_DATA_SIZE, checksum, NETFRAME_DELIMITER)
+    }
+
+    // for a peer accepting up to max_size bytes of data, the extended
+    // header is used above NETFRAME_MAX_DATA_SIZE
+    pub fn encode_delimited(
+        &self,
+        max_size: usize,
+        checksum: NetFrameChecksum,
+        delimiter: u8,
+    ) -> Result<Vec<u8>, NetFrameError> {
+        let mut buffer = vec![0; self.encoded_len_delimited(max_size, checksum)];
+        self.encode_into_delimited(&mut buffer, max_size, checksum, delimiter)?;
+        Ok(buffer)
+    }
+
+    // encodes the frame at the start of the caller supplied buffer,
+    // returns the number of bytes used
+    pub fn encode_into(
+        &self,
+        buffer: &mut [u8],
+    ) -> Result<usize, NetFrameError> {
+        self.encode_into_with(buffer, NetFrameChecksum::None)
+    }
+
+    pub fn encode_into_with(
+        &self,
+        buffer: &mut [u8],
+        checksum: NetFrameChecksum,
+    ) -> Result<usize, NetFrameError> {
+        self.encode_into_delimited(buffer, NETFRAME_MAX_DATA_SIZE, checksum, NETFRAME_DELIMITER)
+    }
+
+    pub fn encode_into_delimited(
+        &self,
+        buffer: &mut [u8],
+        max_size: usize,
+        checksum: NetFrameChecksum,
+        delimiter: u8,
+    ) -> Result<usize, NetFrameError> {
+        let extended = NetFrame::extended(max_size);
+        let header = self.header(max_size, delimiter)?;
+        let header_size = self.header_size(extended);
+        let end = header_size + self.data.len();
+        let size = self.encoded_len_delimited(max_size, checksum);
+        if buffer.len() < size {
+            return Err(NetFrameError::BufferTooSmall);
+        }
+        buffer[..header_size].copy_from_slice(&header[..header_size]);
+        buffer[header_size..end].copy_from_slice(&self.data);
+        if checksum != NetFrameChecksum::None {
+            let trailer = checksum.compute(&[&buffer[..end]]).to_be_bytes();
+            buffer[end..size].copy_from_slice(&trailer);
+        }
+        Ok(size)
+    }
+
+    // encodes the frame straight into the writer,
+    // returns the number of bytes written
+    pub fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<usize, NetFrameError> {
+        self.write_to_with(writer, NetFrameChecksum::None)
+    }
+
+    pub fn write_to_with<W: Write>(
+        &self,
+        writer: &mut W,
+        checksum: NetFrameChecksum,
+    ) -> Result<usize, NetFrameError> {
+        self.write_to_delimited(writer, NETFRAME_MAX_DATA_SIZE, checksum, NETFRAME_DELIMITER)
+    }
+
+    pub fn write_to_delimited<W: Write>(
+        &self,
+        writer: &mut W,
+        max_size: usize,
+        checksum: NetFrameChecksum,
+        delimiter: u8,
+    ) -> Result<usize, NetFrameError> {
+        let header = self.header(max_size, delimiter)?;
+        let header = &header[..self.header_size(NetFrame::extended(max_size))];
+        let trailer = checksum.compute(&[header, &self.data]).to_be_bytes();
+        writer
+            .write_all(header)
+            .and_then(|_| writer.write_all(&self.data))
+            .and_then(|_| writer.write_all(&trailer[..checksum.size()]))
+            .map_err(|err| NetFrameError::WriteFailure(err.kind()))?;
+        Ok(self.encoded_len_delimited(max_size, checksum))
+    }
+
+    // with extended headers enabled 0xFFFF is the marker,
+    // so 65535 bytes already need the extended header
+    fn header_size(
+        &self,
+        extended: bool,
+    ) -> usize {
+        if extended && self.data.len() >= NETFRAME_EXTENDED_LENGTH_MARKER as usize {
+            NETFRAME_EXTENDED_HEADER_SIZE_BYTES
+        } else {
+            NETFRAME_HEADER_SIZE_BYTES
+        }
+    }
+
+    // length is sent in network byte order (big endian),
+    // only the first header_size() bytes are used
+    fn header(
+        &self,
+        max_size: usize,
+        delimiter: u8,
+    ) -> Result<[u8; NETFRAME_EXTENDED_HEADER_SIZE_BYTES], NetFrameError> {
+        if self.data.len() > max_size.min(NETFRAME_EXTENDED_MAX_DATA_SIZE) {
+            return Err(NetFrameError::MessageTooLong);
+        }
+        let mut header = [0; NETFRAME_EXTENDED_HEADER_SIZE_BYTES];
+        header[0] = delimiter;
+        header[1] = self.tag;
+        if self.header_size(NetFrame::extended(max_size)) == NETFRAME_EXTENDED_HEADER_SIZE_BYTES {
+            header[2..4].copy_from_slice(&NETFRAME_EXTENDED_LENGTH_MARKER.to_be_bytes());
+            header[4..8].copy_from_slice(&(self.data.len() as u32).to_be_bytes());
+        } else {
+            header[2..4].copy_from_slice(&(self.data.len() as u16).to_be_bytes());
+        }
+        Ok(header)
+    }
+}
+
+impl NetFrameMetadata {
+    pub fn kind(&self) -> NetFrameTag {
+        NetFrameTag::from(self.tag)
+    }
+
+    // size of the header the frame was sent with
+    pub fn header_size(&self) -> usize {
+        if self.extended {
+            NETFRAME_EXTENDED_HEADER_SIZE_BYTES
+        } else {
+            NETFRAME_HEADER_SIZE_BYTES
+        }
+    }
+}
+
+impl NetFrameChecksum {
+    // size of the trailer
+    pub fn size(&self) -> usize {
+        match self {
+            NetFrameChecksum::None => 0,
+            _ => NETFRAME_CHECKSUM_SIZE_BYTES,
+        }
+    }
+
+    // checksum over all the parts, as if they were one buffer
+    pub fn compute(
+        &self,
+        parts: &[&[u8]],
+    ) -> u32 {
+        match self {
+            NetFrameChecksum::None => 0,
+            NetFrameChecksum::Crc32 => {
+                let mut hasher = crc32fast::Hasher::new();
+                parts.iter().for_each(|part| hasher.update(part));
+                hasher.finalize()
+            }
+            NetFrameChecksum::XxHash32 => {
+                let mut hasher = xxhash_rust::xxh32::Xxh32::new(0);
+                parts.iter().for_each(|part| hasher.update(part));
+                hasher.digest()
+            }
+        }
+    }
 }
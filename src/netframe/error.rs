@@ -28,4 +28,19 @@ pub enum NetFrameError {
 
     #[error("Not enough data to distinguish the frame")]
     TooLittleData,
+
+    #[error("frame data exceeds the maximum frame size")]
+    MessageTooLong,
+
+    #[error("extended length used for data that fits the short header")]
+    InvalidLength,
+
+    #[error("frame checksum does not match the data")]
+    ChecksumMismatch,
+
+    #[error("output buffer is too small to hold the encoded frame")]
+    BufferTooSmall,
+
+    #[error("unable to write the encoded frame: {0:?}")]
+    WriteFailure(std::io::ErrorKind),
 }
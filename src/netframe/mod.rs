@@ -18,5 +18,11 @@ pub mod consts;
 pub mod core;
 pub mod error;
 #[cfg(test)]
+pub mod tests_checksum;
+#[cfg(test)]
+pub mod tests_encode;
+#[cfg(test)]
 pub mod tests_metadata;
+#[cfg(test)]
+pub mod tests_tag;
 pub mod types;
@@ -0,0 +1,77 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use bytes::BytesMut;
+
+use crate::netframe::{
+    error::NetFrameError,
+    types::{NetFrame, NetFrameChecksum},
+};
+
+
+#[test]
+fn frame_checksum_ok_trailer_layout() {
+    let frame = NetFrame::new(0x01, b"123456789".to_vec());
+    let encoded = frame.encode_with(NetFrameChecksum::Crc32).unwrap();
+    assert_eq!(encoded.len(), frame.encoded_len() + 4);
+    // length field does not count the trailer
+    assert_eq!(encoded[..4], [0x00, 0x01, 0x00, 0x09]);
+    // trailer is the checksum of header and data, big endian
+    let expected = crc32fast::hash(&encoded[..13]).to_be_bytes();
+    assert_eq!(encoded[13..], expected);
+    // no trailer without checksum
+    assert_eq!(frame.encode_with(NetFrameChecksum::None), frame.encode());
+}
+
+#[test]
+fn frame_checksum_ok_round_trip() {
+    for checksum in [NetFrameChecksum::Crc32, NetFrameChecksum::XxHash32] {
+        let frame = NetFrame::new(0x02, vec![0xAB; 300]);
+        let encoded = frame.encode_with(checksum).unwrap();
+        // write_to produces the same bytes
+        let mut writer: Vec<u8> = Vec::new();
+        assert_eq!(
+            frame.write_to_with(&mut writer, checksum),
+            Ok(encoded.len())
+        );
+        assert_eq!(writer, encoded);
+        // whole trailer is needed to decode
+        let mut buffer = BytesMut::from(&encoded[..encoded.len() - 1]);
+        assert_eq!(NetFrame::decode(&mut buffer, 1024, checksum), Ok(None));
+        buffer.extend_from_slice(&encoded[encoded.len() - 1..]);
+        assert_eq!(
+            NetFrame::decode(&mut buffer, 1024, checksum),
+            Ok(Some(frame))
+        );
+        assert!(buffer.is_empty());
+    }
+}
+
+#[test]
+fn frame_checksum_failure_flipped_bit() {
+    for checksum in [NetFrameChecksum::Crc32, NetFrameChecksum::XxHash32] {
+        let frame = NetFrame::new(0x02, vec![0xAB; 16]);
+        let mut encoded = frame.encode_with(checksum).unwrap();
+        encoded[10] ^= 0x04;
+        let mut buffer = BytesMut::from(&encoded[..]);
+        assert_eq!(
+            NetFrame::decode(&mut buffer, 1024, checksum),
+            Err(NetFrameError::ChecksumMismatch)
+        );
+        // corrupted frame is left in the buffer
+        assert_eq!(buffer.len(), encoded.len());
+    }
+}
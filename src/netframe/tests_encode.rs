@@ -0,0 +1,224 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use bytes::BytesMut;
+
+use crate::netframe::{
+    consts::{NETFRAME_DELIMITER, NETFRAME_MAX_DATA_SIZE},
+    error::NetFrameError,
+    types::{NetFrame, NetFrameChecksum, NetFrameMetadata},
+};
+
+
+#[test]
+fn frame_encode_ok_header_layout() {
+    // delimiter is 0x00
+    // tag is 0x03
+    // size is 0x0102 in network byte order (258 bytes)
+    let frame = NetFrame::new(0x03, vec![0xAB; 0x0102]);
+    let encoded = frame.encode().unwrap();
+    assert_eq!(encoded[..4], [0x00, 0x03, 0x01, 0x02]);
+    assert_eq!(encoded.len(), 4 + 0x0102);
+    assert_eq!(encoded.len(), frame.encoded_len());
+}
+
+#[test]
+fn frame_encode_ok_round_trip() {
+    let frame = NetFrame::new(0x01, vec![0xDE, 0xAD, 0xBE, 0xEF]);
+    let encoded = frame.encode().unwrap();
+    // decoder sees the same metadata
+    assert_eq!(
+        NetFrame::get_metadata(&encoded),
+        Ok(NetFrameMetadata {
+            tag: 0x01,
+            size: 0x0004,
+            extended: false,
+        })
+    );
+    // and the payload follows the header untouched
+    assert_eq!(encoded[4..], frame.data[..]);
+}
+
+#[test]
+fn frame_encode_ok_round_trip_max_size() {
+    let frame = NetFrame::new(0x00, vec![0x55; NETFRAME_MAX_DATA_SIZE]);
+    let encoded = frame.encode().unwrap();
+    // without extended headers 0xFFFF is a regular length
+    assert_eq!(encoded[..4], [0x00, 0x00, 0xFF, 0xFF]);
+    assert_eq!(
+        NetFrame::get_metadata(&encoded),
+        Ok(NetFrameMetadata {
+            tag: 0x00,
+            size: NETFRAME_MAX_DATA_SIZE as u32,
+            extended: false,
+        })
+    );
+    let mut buffer = BytesMut::from(&encoded[..]);
+    assert_eq!(
+        NetFrame::decode(&mut buffer, NETFRAME_MAX_DATA_SIZE, NetFrameChecksum::None),
+        Ok(Some(frame.clone()))
+    );
+    // and nothing bigger fits the short header
+    let frame = NetFrame::new(0x00, vec![0x55; NETFRAME_MAX_DATA_SIZE + 1]);
+    assert_eq!(frame.encode(), Err(NetFrameError::MessageTooLong));
+}
+
+#[test]
+fn frame_encode_ok_extended_header_when_enabled() {
+    let max_size = 2 * NETFRAME_MAX_DATA_SIZE;
+    // 0xFFFF is the marker, so 65535 bytes already need the extended header
+    let frame = NetFrame::new(0x01, vec![0x55; NETFRAME_MAX_DATA_SIZE]);
+    let encoded = frame
+        .encode_delimited(max_size, NetFrameChecksum::None, NETFRAME_DELIMITER)
+        .unwrap();
+    assert_eq!(
+        encoded[..8],
+        [0x00, 0x01, 0xFF, 0xFF, 0x00, 0x00, 0xFF, 0xFF]
+    );
+    assert_eq!(encoded.len(), 8 + NETFRAME_MAX_DATA_SIZE);
+    let metadata =
+        NetFrame::get_metadata_delimited(&encoded, max_size, NETFRAME_DELIMITER).unwrap();
+    assert_eq!(metadata.size as usize, NETFRAME_MAX_DATA_SIZE);
+    assert_eq!(metadata.header_size(), 8);
+    // write_to produces the same bytes
+    let mut writer: Vec<u8> = Vec::new();
+    assert_eq!(
+        frame.write_to_delimited(&mut writer, max_size, NetFrameChecksum::None, 0x00),
+        Ok(encoded.len())
+    );
+    assert_eq!(writer, encoded);
+    // smaller frames keep the short header
+    let frame = NetFrame::new(0x01, vec![0x55; NETFRAME_MAX_DATA_SIZE - 1]);
+    assert_eq!(
+        frame.encoded_len_delimited(max_size, NetFrameChecksum::None),
+        4 + 0xFFFE
+    );
+}
+
+#[test]
+fn frame_decode_ok_extended_round_trip() {
+    let frame = NetFrame::new(0x01, vec![0x55; 3 * NETFRAME_MAX_DATA_SIZE]);
+    let encoded = frame
+        .encode_delimited(
+            3 * NETFRAME_MAX_DATA_SIZE,
+            NetFrameChecksum::None,
+            NETFRAME_DELIMITER,
+        )
+        .unwrap();
+    let mut buffer = BytesMut::from(&encoded[..]);
+    // whole extended frame is needed
+    let mut partial = buffer.clone();
+    partial.truncate(6);
+    assert_eq!(
+        NetFrame::decode(
+            &mut partial,
+            3 * NETFRAME_MAX_DATA_SIZE,
+            NetFrameChecksum::None
+        ),
+        Ok(None)
+    );
+    assert_eq!(
+        NetFrame::decode(
+            &mut buffer,
+            3 * NETFRAME_MAX_DATA_SIZE,
+            NetFrameChecksum::None
+        ),
+        Ok(Some(frame))
+    );
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn frame_decode_failure_above_max_size() {
+    let frame = NetFrame::new(0x01, vec![0x55; 3 * NETFRAME_MAX_DATA_SIZE]);
+    let encoded = frame
+        .encode_delimited(
+            3 * NETFRAME_MAX_DATA_SIZE,
+            NetFrameChecksum::None,
+            NETFRAME_DELIMITER,
+        )
+        .unwrap();
+    // only the header is needed to reject the frame
+    let mut buffer = BytesMut::from(&encoded[..8]);
+    assert_eq!(
+        NetFrame::decode(
+            &mut buffer,
+            2 * NETFRAME_MAX_DATA_SIZE,
+            NetFrameChecksum::None
+        ),
+        Err(NetFrameError::MessageTooLong)
+    );
+    // encoder refuses it as well
+    assert_eq!(
+        frame.encode_delimited(
+            2 * NETFRAME_MAX_DATA_SIZE,
+            NetFrameChecksum::None,
+            NETFRAME_DELIMITER
+        ),
+        Err(NetFrameError::MessageTooLong)
+    );
+}
+
+#[test]
+fn frame_metadata_failure_extended_length_too_small() {
+    // extended header carrying a length that fits the short one
+    let buffer: Vec<u8> = vec![0x00, 0x01, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x01, 0xAB];
+    let max_size = 2 * NETFRAME_MAX_DATA_SIZE;
+    assert_eq!(
+        NetFrame::get_metadata_delimited(&buffer, max_size, NETFRAME_DELIMITER),
+        Err(NetFrameError::InvalidLength)
+    );
+    // and the marker alone is not enough to tell the length
+    assert_eq!(
+        NetFrame::get_metadata_delimited(&buffer[..6], max_size, NETFRAME_DELIMITER),
+        Err(NetFrameError::TooLittleData)
+    );
+    // peers that did not opt in read it as a regular length
+    assert_eq!(
+        NetFrame::get_metadata(&buffer),
+        Ok(NetFrameMetadata {
+            tag: 0x01,
+            size: 0xFFFF,
+            extended: false,
+        })
+    );
+}
+
+#[test]
+fn frame_encode_into_ok_leaves_tail_untouched() {
+    let frame = NetFrame::new(0x02, vec![0xAB]);
+    let mut buffer = [0xFF; 8];
+    assert_eq!(frame.encode_into(&mut buffer), Ok(5));
+    assert_eq!(buffer, [0x00, 0x02, 0x00, 0x01, 0xAB, 0xFF, 0xFF, 0xFF]);
+}
+
+#[test]
+fn frame_encode_into_failure_buffer_too_small() {
+    let frame = NetFrame::new(0x02, vec![0xAB, 0xCD]);
+    let mut buffer = [0x00; 5];
+    assert_eq!(
+        frame.encode_into(&mut buffer),
+        Err(NetFrameError::BufferTooSmall)
+    );
+}
+
+#[test]
+fn frame_write_to_ok_matches_encode() {
+    let frame = NetFrame::new(0x04, b"hello".to_vec());
+    let mut writer: Vec<u8> = Vec::new();
+    assert_eq!(frame.write_to(&mut writer), Ok(9));
+    assert_eq!(writer, frame.encode().unwrap());
+}
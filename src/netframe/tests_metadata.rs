@@ -42,6 +42,7 @@ fn frame_metadata_ok_proper_metadata_zero_size() {
     let wanted_result: Result<NetFrameMetadata, NetFrameError> = Ok(NetFrameMetadata {
         tag: 0x00,
         size: 0x0000,
+        extended: false,
     });
     // expect Ok
     assert_eq!(NetFrame::get_metadata(&buffer), wanted_result);
@@ -58,6 +59,7 @@ fn frame_metadata_ok_byteorder_test() {
     let wanted_result: Result<NetFrameMetadata, NetFrameError> = Ok(NetFrameMetadata {
         tag: 0x00,
         size: 0x0100,
+        extended: false,
     });
     // expect Ok
     assert_eq!(NetFrame::get_metadata(&buffer), wanted_result);
@@ -0,0 +1,78 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use crate::netframe::{
+    consts::{NETFRAME_TAG_APPLICATION_FIRST, NETFRAME_TAG_APPLICATION_LAST},
+    types::{NetFrame, NetFrameTag},
+};
+
+
+#[test]
+fn frame_tag_ok_lossless_round_trip() {
+    // every byte survives the conversion to the typed tag and back
+    for byte in 0..=u8::MAX {
+        assert_eq!(u8::from(NetFrameTag::from(byte)), byte);
+    }
+}
+
+#[test]
+fn frame_tag_ok_known_unknown_and_application() {
+    assert_eq!(NetFrameTag::from(0x06), NetFrameTag::Ping);
+    assert_eq!(NetFrameTag::from(0xFF), NetFrameTag::Undefined);
+    // unassigned tag keeps its value
+    for byte in [0x09, 0x7F] {
+        let NetFrameTag::Unknown(value) = NetFrameTag::from(byte) else {
+            panic!("not unknown: {}", byte);
+        };
+        assert_eq!(value.get(), byte);
+    }
+    // application range
+    assert_eq!(
+        NetFrameTag::from(NETFRAME_TAG_APPLICATION_FIRST),
+        NetFrameTag::application(NETFRAME_TAG_APPLICATION_FIRST).unwrap()
+    );
+    assert_eq!(
+        NetFrameTag::from(NETFRAME_TAG_APPLICATION_LAST),
+        NetFrameTag::application(NETFRAME_TAG_APPLICATION_LAST).unwrap()
+    );
+    assert_eq!(format!("{:?}", NetFrameTag::from(0x80)), "Application(128)");
+}
+
+#[test]
+fn frame_tag_ok_application_constructor() {
+    let Some(NetFrameTag::Application(value)) = NetFrameTag::application(0x80) else {
+        panic!("not application");
+    };
+    assert_eq!(value.get(), 0x80);
+    // reserved and unassigned bytes can not be application tags
+    assert_eq!(NetFrameTag::application(0x03), None);
+    assert_eq!(NetFrameTag::application(0x7F), None);
+    assert_eq!(NetFrameTag::application(0xFF), None);
+}
+
+#[test]
+fn frame_kind_ok_with_kind_and_reencode() {
+    let frame = NetFrame::with_kind(NetFrameTag::Pong, vec![0xAB]);
+    assert_eq!(frame.tag, 0x07);
+    assert_eq!(frame.kind(), NetFrameTag::Pong);
+    // unknown tag survives decode and re-encode
+    let encoded: Vec<u8> = vec![0x00, 0x42, 0x00, 0x01, 0xAB];
+    let metadata = NetFrame::get_metadata(&encoded).unwrap();
+    assert_eq!(metadata.kind(), NetFrameTag::from(0x42));
+    assert!(matches!(metadata.kind(), NetFrameTag::Unknown(_)));
+    let frame = NetFrame::with_kind(metadata.kind(), vec![0xAB]);
+    assert_eq!(frame.encode().unwrap(), encoded);
+}
@@ -20,17 +20,44 @@ limitations under the License.
 // │ delimiter │  tag  │ length │   data   │
 // └───────────┴───────┴────────┴──────────┘
 //
-// * delimiter is always 0x00
+// * delimiter is 0x00 unless configured otherwise, both peers have to agree
 // * tag is optional and is passed to handlers
 // * length is the length of the data
 // * data is the actual data, max 65535 bytes
 //
+// extended length variant, opt in, for data larger than 65535 bytes:
+// ┌───────────┬───────┬────────┬──────────┬──────────┐
+// │   8bit    │ 8bit  │ 16bit  │  32bit   │ (length) │
+// │           │       │        │          │          │
+// │ delimiter │  tag  │ 0xFFFF │  length  │   data   │
+// └───────────┴───────┴────────┴──────────┴──────────┘
+//
+// * used only when both peers raise their max frame size above 65535, otherwise
+//   0xFFFF is a regular length and larger frames are rejected with
+//   MessageTooLong
+// * with it enabled 0xFFFF in the short length field marks the extended header,
+//   so the short header carries at most 65534 bytes
+// * length is the length of the data, always above 65534
+//
+// optional checksum trailer, both peers have to use the same mode:
+// ┌──────────┬──────────┬──────────┐
+// │ (header) │ (length) │  32bit   │
+// │          │          │          │
+// │  header  │   data   │ checksum │
+// └──────────┴──────────┴──────────┘
+//
+// * checksum covers the header and the data, network byte order
+// * length field does not include the trailer
+//
 // During write, stream will try to decode the messages and push
 // them to the message buffer
 //
-// NetStream can hold up to 256 messages, subsequent writes will fail
+// NetStream can hold up to 256 messages, subsequent frames are kept
+// in the byte buffer until next() makes room for them
 // NetStream will buffer up to 3*65535 bytes, if the buffer is full
 // or can not be decoded, supsequent writes will fail.
+// write() reports how many bytes were accepted, the rest has to be
+// written again after draining the stream
 //
 // call to next() will take one message from the stack and return it in
 // decoded form (as a struct)
@@ -43,9 +70,17 @@ limitations under the License.
 // the rest of the data is passed as is
 // tags are passed to the conection handler with the message
 // those are optional
+// tag byte is stored as is, NetFrameTag is the typed view of it
+// (NetFrame::kind()), conversion between the two is lossless
+
+
+use bytes::Bytes;
+use serde_derive::{Deserialize, Serialize};
 
+use crate::netframe::consts::{NETFRAME_TAG_APPLICATION_FIRST, NETFRAME_TAG_APPLICATION_LAST};
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum NetFrameTag {
     // Undefiled, no special tags, just a message, use if you are too lazy
     GenericMessage,
@@ -55,16 +90,17 @@ pub enum NetFrameTag {
 
     // message is split into multiple packets
     // first N bytes in data should be packet identifiers,
-    // but its on the protocol handler to define that
+    // see netfragment for the layout used by NetFragmenter/NetReassembler
     MultiMessage,
 
     // message to the connection handler
     Control,
 
-    // connection established
+    // connection established, carries the session handshake,
+    // see netsession for the layout
     Hello,
 
-    // connection closed
+    // connection closed, carries the reason
     Goodbye,
 
     // ping
@@ -73,13 +109,56 @@ pub enum NetFrameTag {
     // ping response
     Pong,
 
-    // reset request/notification
+    // reset request/acknowledgement, see netsession for the semantics
     Reset,
 
+    // application defined tag, value is the tag byte itself and is always
+    // in NETFRAME_TAG_APPLICATION_FIRST..=NETFRAME_TAG_APPLICATION_LAST,
+    // use NetFrameTag::application() to build it
+    Application(NetFrameTagValue),
+
+    // tag byte not assigned to anything (yet), kept as is so the frame
+    // can be passed on without losing it, only From<u8> builds it
+    Unknown(NetFrameTagValue),
+
     // undefined tag
     Undefined,
 }
 
+// tag byte of Application and Unknown, the field is private so the
+// value always matches the variant, i.e. Application(0x03) can not be
+// built and sent as a Control frame
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct NetFrameTagValue(u8);
+
+impl NetFrameTagValue {
+    pub fn get(self) -> u8 {
+        self.0
+    }
+}
+
+// prints just the byte, Application(128) rather than the wrapper
+impl std::fmt::Debug for NetFrameTagValue {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl NetFrameTag {
+    // returns None if the value is outside of the application range
+    pub fn application(value: u8) -> Option<Self> {
+        match value {
+            NETFRAME_TAG_APPLICATION_FIRST..=NETFRAME_TAG_APPLICATION_LAST => {
+                Some(NetFrameTag::Application(NetFrameTagValue(value)))
+            }
+            _ => None,
+        }
+    }
+}
+
 impl From<u8> for NetFrameTag {
     fn from(byte: u8) -> Self {
         match byte {
@@ -92,7 +171,11 @@ impl From<u8> for NetFrameTag {
             0x06 => NetFrameTag::Ping,
             0x07 => NetFrameTag::Pong,
             0x08 => NetFrameTag::Reset,
-            _ => NetFrameTag::Undefined,
+            NETFRAME_TAG_APPLICATION_FIRST..=NETFRAME_TAG_APPLICATION_LAST => {
+                NetFrameTag::Application(NetFrameTagValue(byte))
+            }
+            0xFF => NetFrameTag::Undefined,
+            _ => NetFrameTag::Unknown(NetFrameTagValue(byte)),
         }
     }
 }
@@ -109,19 +192,40 @@ impl From<NetFrameTag> for u8 {
             NetFrameTag::Ping => 0x06,
             NetFrameTag::Pong => 0x07,
             NetFrameTag::Reset => 0x08,
+            NetFrameTag::Application(value) => value.get(),
+            NetFrameTag::Unknown(value) => value.get(),
             NetFrameTag::Undefined => 0xFF,
         }
     }
 }
 
+// checksum carried in the frame trailer
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetFrameChecksum {
+    // no trailer
+    #[default]
+    None,
+
+    // CRC-32 (IEEE)
+    Crc32,
+
+    // xxHash32, seed 0
+    #[serde(rename = "xxhash32")]
+    XxHash32,
+}
+
 #[derive(Debug, Default, PartialEq)]
 pub struct NetFrameMetadata {
     pub tag: u8,
-    pub size: u16,
+    pub size: u32,
+    // sent with the extended header
+    pub extended: bool,
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct NetFrame {
     pub tag: u8,
-    pub data: Vec<u8>,
+    // shared view into the stream buffer, cloning it does not copy the data
+    pub data: Bytes,
 }
@@ -0,0 +1,471 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use reedline_repl_rs::Repl;
+use tokio::{runtime::Handle, sync::mpsc, time::Instant};
+
+use super::{
+    consts::{NETREPL_MAX_PENDING_FRAMES, NETREPL_TIMEOUT_MILLIS},
+    error::NetReplError,
+    types::{NetReplConnection, NetReplContext, NetReplInbox, NetReplTail},
+};
+use crate::{
+    netcli::core::{decode_hex, enum_parser, format_payload, parse_tag},
+    netclient::types::NetStreamClient,
+    netcodec::types::NetFrameCodec,
+    netcontrol::types::NetControl,
+    netframe::types::{NetFrame, NetFrameChecksum, NetFrameTag},
+    netsession::types::NetGoodbyeReason,
+};
+
+impl NetReplContext {
+    pub fn new(
+        handle: Handle,
+        codec: NetFrameCodec,
+    ) -> Self {
+        Self {
+            handle,
+            codec,
+            connection: None,
+            timeout: Duration::from_millis(NETREPL_TIMEOUT_MILLIS),
+            max_pending_frames: NETREPL_MAX_PENDING_FRAMES,
+        }
+    }
+
+    pub fn connect(
+        &mut self,
+        address: SocketAddr,
+        codec: NetFrameCodec,
+        handshake: bool,
+    ) -> Result<String, NetReplError> {
+        if let Some(connection) = &self.connection {
+            return Err(NetReplError::Connected(connection.address.to_string()));
+        }
+        let client = self.handle.block_on(async {
+            let mut client = NetStreamClient::with_codec(address, codec).await?;
+            if handshake {
+                client.handshake().await?;
+            }
+            Ok::<_, NetReplError>(client)
+        })?;
+        let established = match client.session.established() {
+            true => format!(", session {}", client.session.session_id()),
+            false => String::new(),
+        };
+        let (outgoing, requests) = mpsc::unbounded_channel();
+        let incoming = Arc::new(NetReplInbox::new(self.max_pending_frames));
+        let task = self.handle.spawn(pump(client, requests, incoming.clone()));
+        self.connection = Some(NetReplConnection {
+            address,
+            outgoing,
+            incoming,
+            task,
+        });
+        Ok(format!("connected to {}{}", address, established))
+    }
+
+    pub fn disconnect(&mut self) -> Result<String, NetReplError> {
+        let connection = self.connection.take().ok_or(NetReplError::NotConnected)?;
+        drop(connection.outgoing);
+        let _ = self.handle.block_on(connection.task);
+        Ok(format!("disconnected from {}", connection.address))
+    }
+
+    pub fn send(
+        &mut self,
+        frame: NetFrame,
+    ) -> Result<String, NetReplError> {
+        let size = frame.data.len();
+        self.connection()?
+            .outgoing
+            .send(frame)
+            .map_err(|_| NetReplError::Closed)?;
+        Ok(format!("sent {} bytes", size))
+    }
+
+    // sends the request and waits for the Control frame answering it,
+    // anything else received meanwhile is kept for tail
+    pub fn control(
+        &mut self,
+        request: NetControl,
+    ) -> Result<NetControl, NetReplError> {
+        let deadline = Instant::now() + self.timeout;
+        let handle = self.handle.clone();
+        let connection = self.connection()?;
+        connection
+            .outgoing
+            .send(request.to_frame())
+            .map_err(|_| NetReplError::Closed)?;
+        let reply = handle.block_on(async {
+            let control = connection
+                .incoming
+                .take(|frame| frame.kind() == NetFrameTag::Control);
+            tokio::time::timeout_at(deadline, control).await
+        });
+        match reply {
+            Ok(Some(frame)) => Ok(NetControl::from_frame(&frame)?),
+            Ok(None) => Err(NetReplError::Closed),
+            Err(_) => Err(NetReplError::Timeout),
+        }
+    }
+
+    pub fn list(&mut self) -> Result<String, NetReplError> {
+        match self.control(NetControl::ListConnections)? {
+            NetControl::Connections {
+                connections,
+            } if connections.is_empty() => Ok("no connections".to_string()),
+            NetControl::Connections {
+                connections,
+            } => {
+                let lines: Vec<String> = connections
+                    .iter()
+                    .map(|info| {
+                        format!(
+                            "{} {} {}{}",
+                            info.id,
+                            info.address,
+                            info.session_id,
+                            if info.paused { " paused" } else { "" }
+                        )
+                    })
+                    .collect();
+                Ok(lines.join("\n"))
+            }
+            reply => Err(unexpected(reply)),
+        }
+    }
+
+    pub fn stats(
+        &mut self,
+        id: Option<usize>,
+    ) -> Result<String, NetReplError> {
+        match self.control(NetControl::QueryStats {
+            id,
+        })? {
+            NetControl::Stats(stats) => {
+                serde_json::to_string_pretty(&stats)
+                    .map_err(|err| NetReplError::Unexpected(err.to_string()))
+            }
+            reply => Err(unexpected(reply)),
+        }
+    }
+
+    // frames received so far, then whatever arrives until count frames
+    // were collected or timeout passed. Also tells how many frames were
+    // dropped since the last call, for lack of room.
+    pub fn tail(
+        &mut self,
+        count: Option<usize>,
+        timeout: Option<Duration>,
+    ) -> Result<NetReplTail, NetReplError> {
+        let deadline = Instant::now() + timeout.unwrap_or(self.timeout);
+        let handle = self.handle.clone();
+        let incoming = &self.connection()?.incoming;
+        let limit = count.unwrap_or(usize::MAX);
+        let mut frames = Vec::new();
+        let closed = handle.block_on(async {
+            while frames.len() < limit {
+                match tokio::time::timeout_at(deadline, incoming.take(|_| true)).await {
+                    Ok(Some(frame)) => frames.push(frame),
+                    Ok(None) => return true,
+                    Err(_) => break,
+                }
+            }
+            false
+        });
+        let dropped = incoming.take_dropped();
+        if closed && frames.is_empty() && dropped == 0 {
+            return Err(NetReplError::Closed);
+        }
+        Ok(NetReplTail {
+            frames,
+            dropped,
+        })
+    }
+
+    fn connection(&mut self) -> Result<&mut NetReplConnection, NetReplError> {
+        self.connection.as_mut().ok_or(NetReplError::NotConnected)
+    }
+}
+
+impl NetReplInbox {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ..Default::default()
+        }
+    }
+
+    // queues the frame, dropping the oldest one when full
+    pub fn push(
+        &self,
+        frame: NetFrame,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        if state.frames.len() >= self.capacity {
+            state.frames.pop_front();
+            state.dropped += 1;
+        }
+        state.frames.push_back(frame);
+        drop(state);
+        self.notify.notify_one();
+    }
+
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.notify.notify_one();
+    }
+
+    // waits for the first frame matching, the others stay queued in
+    // order. None once the task is done and nothing matches.
+    pub async fn take(
+        &self,
+        matching: impl Fn(&NetFrame) -> bool,
+    ) -> Option<NetFrame> {
+        loop {
+            let notified = self.notify.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if let Some(position) = state.frames.iter().position(&matching) {
+                    return state.frames.remove(position);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    // number of frames dropped since the last call
+    pub fn take_dropped(&self) -> usize {
+        std::mem::take(&mut self.state.lock().unwrap().dropped)
+    }
+}
+
+fn unexpected(reply: NetControl) -> NetReplError {
+    match reply {
+        NetControl::Error {
+            message,
+        } => NetReplError::Refused(message),
+        reply => NetReplError::Unexpected(format!("{:?}", reply)),
+    }
+}
+
+// owns the client: sends what the repl asks for and forwards what it
+// receives, the client answers pings on its own. Says goodbye once the repl
+// drops its sender.
+async fn pump(
+    mut client: NetStreamClient,
+    mut requests: mpsc::UnboundedReceiver<NetFrame>,
+    incoming: Arc<NetReplInbox>,
+) {
+    loop {
+        tokio::select! {
+            request = requests.recv() => match request {
+                Some(frame) => {
+                    if let Err(err) = client.send_frame(frame).await {
+                        tracing::warn!("repl send failed: {}", err);
+                        break;
+                    }
+                }
+                None => {
+                    let _ = match client.session.established() {
+                        true => client.goodbye(NetGoodbyeReason::Normal).await,
+                        false => client.close().await,
+                    };
+                    break;
+                }
+            },
+            frame = client.next_frame() => match frame {
+                Some(Ok(frame)) => incoming.push(frame),
+                Some(Err(err)) => {
+                    tracing::warn!("repl receive failed: {}", err);
+                    break;
+                }
+                None => break,
+            },
+        }
+    }
+    incoming.close();
+}
+
+pub fn format_frame(
+    frame: &NetFrame,
+    hex: bool,
+) -> String {
+    format!(
+        "{:?} {} {}",
+        frame.kind(),
+        frame.data.len(),
+        format_payload(&frame.data, hex)
+    )
+}
+
+// repl with all the commands registered, run() it for an interactive
+// console or run_with_reader() to execute a script
+pub fn repl(context: NetReplContext) -> Repl<NetReplContext, NetReplError> {
+    Repl::new(context)
+        .with_name("netstream")
+        .with_version(env!("CARGO_PKG_VERSION"))
+        .with_description("netframe admin console")
+        .with_stop_on_ctrl_c(true)
+        .with_command(
+            Command::new("connect")
+                .about("connect to a server")
+                .arg(
+                    Arg::new("address")
+                        .required(true)
+                        .value_parser(value_parser!(SocketAddr)),
+                )
+                .arg(
+                    Arg::new("handshake")
+                        .long("handshake")
+                        .action(ArgAction::SetTrue)
+                        .help("exchange Hello after connecting"),
+                )
+                .arg(
+                    Arg::new("checksum")
+                        .long("checksum")
+                        .value_parser(enum_parser::<NetFrameChecksum>(&[
+                            "none", "crc32", "xxhash32",
+                        ]))
+                        .help("frame trailer, has to match the server"),
+                ),
+            connect_command,
+        )
+        .with_command(
+            Command::new("disconnect").about("say goodbye and close the connection"),
+            |_, context| context.disconnect().map(Some),
+        )
+        .with_command(
+            Command::new("send")
+                .about("send a frame")
+                .arg(
+                    Arg::new("tag")
+                        .required(true)
+                        .value_parser(parse_tag)
+                        .help("tag name (i.e. single-message) or value (i.e. 0x80)"),
+                )
+                .arg(Arg::new("data").help("frame data, empty frame if omitted"))
+                .arg(
+                    Arg::new("hex")
+                        .long("hex")
+                        .action(ArgAction::SetTrue)
+                        .help("data is hex encoded instead of utf8"),
+                ),
+            send_command,
+        )
+        .with_command(
+            Command::new("list").about("list connections of the server"),
+            |_, context| context.list().map(Some),
+        )
+        .with_command(
+            Command::new("stats")
+                .about("traffic counters of a connection, this one if no id is given")
+                .arg(Arg::new("id").value_parser(value_parser!(usize))),
+            |args, context| {
+                context
+                    .stats(args.get_one::<usize>("id").copied())
+                    .map(Some)
+            },
+        )
+        .with_command(
+            Command::new("tail")
+                .about("print received frames")
+                .arg(
+                    Arg::new("count")
+                        .value_parser(value_parser!(usize))
+                        .help("stop after this many frames"),
+                )
+                .arg(
+                    Arg::new("timeout")
+                        .long("timeout")
+                        .value_parser(value_parser!(u64))
+                        .help("milliseconds to wait for frames"),
+                )
+                .arg(
+                    Arg::new("hex")
+                        .long("hex")
+                        .action(ArgAction::SetTrue)
+                        .help("print data as hex even if it is valid utf8"),
+                ),
+            tail_command,
+        )
+}
+
+fn connect_command(
+    args: ArgMatches,
+    context: &mut NetReplContext,
+) -> Result<Option<String>, NetReplError> {
+    let address = args
+        .get_one::<SocketAddr>("address")
+        .copied()
+        .ok_or_else(|| NetReplError::Invalid("address".to_string()))?;
+    let mut codec = context.codec.clone();
+    if let Some(checksum) = args.get_one::<NetFrameChecksum>("checksum") {
+        codec.checksum = *checksum;
+    }
+    context
+        .connect(address, codec, args.get_flag("handshake"))
+        .map(Some)
+}
+
+fn send_command(
+    args: ArgMatches,
+    context: &mut NetReplContext,
+) -> Result<Option<String>, NetReplError> {
+    let tag = args
+        .get_one::<NetFrameTag>("tag")
+        .copied()
+        .ok_or_else(|| NetReplError::Invalid("tag".to_string()))?;
+    let data = match args.get_one::<String>("data") {
+        Some(data) if args.get_flag("hex") => {
+            decode_hex(data).map_err(|err| NetReplError::Invalid(err.to_string()))?
+        }
+        Some(data) => data.as_bytes().to_vec(),
+        None => Vec::new(),
+    };
+    context.send(NetFrame::with_kind(tag, data)).map(Some)
+}
+
+fn tail_command(
+    args: ArgMatches,
+    context: &mut NetReplContext,
+) -> Result<Option<String>, NetReplError> {
+    let count = args.get_one::<usize>("count").copied();
+    let timeout = args
+        .get_one::<u64>("timeout")
+        .map(|millis| Duration::from_millis(*millis));
+    let hex = args.get_flag("hex");
+    let tail = context.tail(count, timeout)?;
+    let mut lines: Vec<String> = tail
+        .frames
+        .iter()
+        .map(|frame| format_frame(frame, hex))
+        .collect();
+    // they were older than anything shown
+    if tail.dropped > 0 {
+        lines.insert(0, format!("{} frames dropped", tail.dropped));
+    }
+    if lines.is_empty() {
+        return Ok(Some("no frames".to_string()));
+    }
+    Ok(Some(lines.join("\n")))
+}
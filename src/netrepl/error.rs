@@ -0,0 +1,63 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use thiserror::Error;
+
+use crate::{
+    netcontrol::error::NetControlError,
+    netsession::error::NetSessionError,
+    netstream::error::NetStreamErr,
+};
+
+
+#[derive(Error, Debug)]
+pub enum NetReplError {
+    #[error("not connected, use connect first")]
+    NotConnected,
+
+    #[error("already connected to {0}")]
+    Connected(String),
+
+    #[error("connection closed by the server")]
+    Closed,
+
+    #[error("no reply from the server")]
+    Timeout,
+
+    #[error("server refused: {0}")]
+    Refused(String),
+
+    #[error("unexpected reply: {0}")]
+    Unexpected(String),
+
+    #[error("invalid argument: {0}")]
+    Invalid(String),
+
+    #[error(transparent)]
+    Repl(#[from] reedline_repl_rs::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Stream(#[from] NetStreamErr),
+
+    #[error(transparent)]
+    Session(#[from] NetSessionError),
+
+    #[error(transparent)]
+    Control(#[from] NetControlError),
+}
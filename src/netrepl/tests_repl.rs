@@ -0,0 +1,234 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::{io::Cursor, net::SocketAddr, time::Duration};
+
+use tokio::{
+    runtime::{Builder, Runtime},
+    task::JoinHandle,
+};
+
+use super::{
+    core::{format_frame, repl},
+    error::NetReplError,
+    types::{NetReplContext, NetReplTail},
+};
+use crate::{
+    netcodec::types::NetFrameCodec,
+    netframe::types::{NetFrame, NetFrameTag},
+    netserver::{
+        tests_netserver::Recorder,
+        types::{NetServerConfig, NetServerShutdown, NetStreamServer},
+    },
+};
+
+
+struct Server {
+    runtime: Runtime,
+    address: SocketAddr,
+    recorder: Recorder,
+    shutdown: NetServerShutdown,
+    running: JoinHandle<std::io::Result<()>>,
+}
+
+impl Server {
+    fn start(echo: bool) -> Self {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let recorder = Recorder {
+            echo,
+            ..Default::default()
+        };
+        let handler = recorder.clone();
+        let config = NetServerConfig {
+            control: true,
+            control_admins: vec!["127.0.0.1".parse().unwrap()],
+            ..Default::default()
+        };
+        let server = runtime
+            .block_on(NetStreamServer::with_config(
+                "127.0.0.1:0".parse().unwrap(),
+                config,
+                move || handler.clone(),
+            ))
+            .unwrap();
+        let address = server.local_addr().unwrap();
+        let shutdown = server.shutdown_handle();
+        let running = runtime.spawn(server.run());
+        Self {
+            runtime,
+            address,
+            recorder,
+            shutdown,
+            running,
+        }
+    }
+
+    fn context(&self) -> NetReplContext {
+        NetReplContext::new(self.runtime.handle().clone(), NetFrameCodec::default())
+    }
+
+    fn wait_for_frames(
+        &self,
+        count: usize,
+    ) -> Vec<NetFrame> {
+        for _ in 0..100 {
+            if self.recorder.frames().len() >= count {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        self.recorder.frames()
+    }
+
+    fn stop(self) {
+        self.shutdown.shutdown();
+        self.runtime.block_on(self.running).unwrap().unwrap();
+    }
+}
+
+#[test]
+fn netrepl_ok_commands() {
+    let server = Server::start(false);
+    let mut context = server.context();
+    let connected = context
+        .connect(server.address, NetFrameCodec::default(), true)
+        .unwrap();
+    assert!(connected.contains("session"));
+    let frame = NetFrame::with_kind(NetFrameTag::SingleMessage, "hello");
+    assert_eq!(context.send(frame.clone()).unwrap(), "sent 5 bytes");
+    assert_eq!(server.wait_for_frames(1), vec![frame]);
+    let list = context.list().unwrap();
+    assert_eq!(list.lines().count(), 1);
+    assert!(list.starts_with("1 "));
+    let stats = context.stats(None).unwrap();
+    assert!(stats.contains("\"frames_received\""));
+    assert!(context.disconnect().unwrap().starts_with("disconnected"));
+    server.stop();
+}
+
+#[test]
+fn netrepl_ok_tail() {
+    let server = Server::start(true);
+    let mut context = server.context();
+    context
+        .connect(server.address, NetFrameCodec::default(), false)
+        .unwrap();
+    let frames: Vec<NetFrame> = (0..3u8)
+        .map(|i| NetFrame::with_kind(NetFrameTag::from(0x80 + i), vec![i; 4]))
+        .collect();
+    for frame in &frames {
+        context.send(frame.clone()).unwrap();
+    }
+    let tailed = context.tail(Some(2), None).unwrap();
+    assert_eq!(tailed.frames, frames[..2]);
+    assert_eq!(tailed.dropped, 0);
+    // the rest arrives as it is, nothing more within the timeout
+    let tailed = context
+        .tail(None, Some(Duration::from_millis(200)))
+        .unwrap();
+    assert_eq!(tailed.frames, frames[2..]);
+    assert_eq!(
+        format_frame(&frames[2], false),
+        "Application(130) 4 02020202"
+    );
+    context.disconnect().unwrap();
+    server.stop();
+}
+
+#[test]
+fn netrepl_ok_tail_drops_oldest() {
+    let server = Server::start(true);
+    let mut context = server.context();
+    context.max_pending_frames = 2;
+    context
+        .connect(server.address, NetFrameCodec::default(), false)
+        .unwrap();
+    let frames: Vec<NetFrame> = (0..5u8)
+        .map(|i| NetFrame::with_kind(NetFrameTag::from(0x80 + i), vec![i; 4]))
+        .collect();
+    for frame in &frames {
+        context.send(frame.clone()).unwrap();
+    }
+    // control reply comes after the echoes, pushing all but the last out
+    context.list().unwrap();
+    let tailed = context
+        .tail(None, Some(Duration::from_millis(200)))
+        .unwrap();
+    assert_eq!(tailed.frames, frames[4..]);
+    assert_eq!(tailed.dropped, 4);
+    // reported once
+    let tailed = context
+        .tail(None, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(tailed, NetReplTail::default());
+    context.disconnect().unwrap();
+    server.stop();
+}
+
+#[test]
+fn netrepl_ok_script() {
+    let server = Server::start(false);
+    let script = format!(
+        "connect {} --handshake\nsend single \"hello there\"\nsend 0x80 beef --hex\ndisconnect\n",
+        server.address
+    );
+    let mut repl = repl(server.context());
+    repl.run_with_reader(Cursor::new(script)).unwrap();
+    assert!(repl.context().connection.is_none());
+    assert_eq!(
+        server.wait_for_frames(2),
+        vec![
+            NetFrame::with_kind(NetFrameTag::SingleMessage, "hello there"),
+            NetFrame::with_kind(NetFrameTag::from(0x80), vec![0xBE, 0xEF]),
+        ]
+    );
+    server.stop();
+}
+
+#[test]
+fn netrepl_failure_connection() {
+    let server = Server::start(false);
+    let mut context = server.context();
+    let frame = NetFrame::with_kind(NetFrameTag::SingleMessage, "hello");
+    assert!(matches!(
+        context.send(frame),
+        Err(NetReplError::NotConnected)
+    ));
+    assert!(matches!(context.list(), Err(NetReplError::NotConnected)));
+    assert!(matches!(
+        context.disconnect(),
+        Err(NetReplError::NotConnected)
+    ));
+    context
+        .connect(server.address, NetFrameCodec::default(), false)
+        .unwrap();
+    assert!(matches!(
+        context.connect(server.address, NetFrameCodec::default(), false),
+        Err(NetReplError::Connected(_))
+    ));
+    // server says goodbye on shutdown, after that there is nothing to read
+    server.shutdown.shutdown();
+    let tailed = context.tail(None, None).unwrap().frames;
+    assert_eq!(tailed.len(), 1);
+    assert_eq!(tailed[0].kind(), NetFrameTag::Goodbye);
+    assert!(matches!(
+        context.tail(None, None),
+        Err(NetReplError::Closed)
+    ));
+    assert!(matches!(context.list(), Err(NetReplError::Closed)));
+    context.disconnect().unwrap();
+    server.runtime.block_on(server.running).unwrap().unwrap();
+}
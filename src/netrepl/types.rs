@@ -0,0 +1,85 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::{
+    collections::VecDeque,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::{
+    runtime::Handle,
+    sync::{mpsc, Notify},
+    task::JoinHandle,
+};
+
+use crate::{netcodec::types::NetFrameCodec, netframe::types::NetFrame};
+
+
+// state kept between repl commands. Commands run on the repl thread and
+// block on the runtime behind handle, so it must not be called from
+// inside of an async task, use spawn_blocking or a plain thread.
+#[derive(Debug)]
+pub struct NetReplContext {
+    pub handle: Handle,
+    // frame size limit and checksum used by connect, unless overridden
+    pub codec: NetFrameCodec,
+    pub connection: Option<NetReplConnection>,
+    // how long to wait for control replies and, by default, tailed frames
+    pub timeout: Duration,
+    // received frames kept for tail, older ones are dropped past it
+    pub max_pending_frames: usize,
+}
+
+// connection to a server, the client itself is owned by a task that
+// answers keepalive pings and forwards everything else to incoming
+#[derive(Debug)]
+pub struct NetReplConnection {
+    pub address: SocketAddr,
+    // dropping it says goodbye (or just closes) and ends the task
+    pub outgoing: mpsc::UnboundedSender<NetFrame>,
+    pub incoming: Arc<NetReplInbox>,
+    pub task: JoinHandle<()>,
+}
+
+// frames received but not shown yet, filled by the connection task.
+// Control replies are taken out of it, the rest is left for tail.
+// Once full the oldest frame is dropped and counted, so a chatty server
+// can not grow it while nobody tails.
+#[derive(Debug, Default)]
+pub struct NetReplInbox {
+    pub state: Mutex<NetReplInboxState>,
+    pub notify: Notify,
+    pub capacity: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct NetReplInboxState {
+    pub frames: VecDeque<NetFrame>,
+    // frames dropped since tail last reported them
+    pub dropped: usize,
+    // connection task is done, nothing more will arrive
+    pub closed: bool,
+}
+
+// what tail collected
+#[derive(Debug, Default, PartialEq)]
+pub struct NetReplTail {
+    pub frames: Vec<NetFrame>,
+    // frames dropped for lack of room before they could be shown
+    pub dropped: usize,
+}
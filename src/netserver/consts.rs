@@ -0,0 +1,34 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+#[cfg(feature = "mio")]
+use mio::Token;
+
+pub const NETSERVER_DEFAULT_ADDRESS: &str = "127.0.0.1:6669";
+pub const NETSERVER_EVENTS_CAPACITY: usize = 128;
+// poll timeout, connections are ticked at least this often
+#[cfg(feature = "mio")]
+pub const NETSERVER_TICK_MILLIS: u64 = 250;
+pub const NETSERVER_READ_CHUNK_SIZE: usize = 4096;
+pub const NETSERVER_MAX_OUTBOUND_BYTES: usize = 1 << 20;
+// how long a connection may stall flushing its Goodbye on shutdown
+pub const NETSERVER_SHUTDOWN_LINGER_MILLIS: u64 = 500;
+// shortest keepalive interval a peer may ask for over the control channel
+pub const NETSERVER_MIN_KEEPALIVE_MILLIS: u64 = 1000;
+// pause after accept() failed for lack of descriptors or memory
+pub const NETSERVER_ACCEPT_BACKOFF_MILLIS: u64 = 100;
+#[cfg(feature = "mio")]
+pub const NETSERVER_LISTENER_TOKEN: Token = Token(0);
@@ -0,0 +1,1194 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+#[cfg(feature = "mio")]
+use std::io::{Read, Write};
+use std::{
+    collections::BTreeMap,
+    io,
+    net::SocketAddr,
+    sync::{atomic::Ordering, Arc, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use bytes::{Buf, Bytes, BytesMut};
+#[cfg(feature = "mio")]
+use mio::{net::TcpListener, Events, Interest, Poll, Registry, Token};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    task::JoinSet,
+};
+use uuid::Uuid;
+
+#[cfg(feature = "mio")]
+use crate::netserver::types::{NetMioConnection, NetServer};
+use crate::{
+    logger,
+    netcontrol::types::{NetConnectionInfo, NetConnectionStats, NetControl, NetControlOption},
+    netfragment::types::NetReassembler,
+    netframe::types::{NetFrame, NetFrameTag},
+    netserver::{
+        consts::*,
+        types::{
+            ConnectionHandler,
+            NetConnection,
+            NetConnectionContext,
+            NetConnectionShared,
+            NetOutbound,
+            NetOutboundPolicy,
+            NetRegistry,
+            NetServerConfig,
+            NetServerShutdown,
+            NetStreamServer,
+        },
+    },
+    netsession::{
+        consts::NETSESSION_KEEPALIVE_MAX_MISSED,
+        error::NetSessionError,
+        types::{
+            NetGoodbyeReason,
+            NetHello,
+            NetKeepalive,
+            NetKeepaliveConfig,
+            NetKeepaliveEvent,
+            NetReset,
+            NetRttStats,
+            NetSession,
+        },
+    },
+    netstream::{
+        error::{NetStreamErr, NetStreamErrorType},
+        types::{FramingStream, NetStream, NetStreamState},
+    },
+};
+
+impl Default for NetServerConfig {
+    fn default() -> Self {
+        Self {
+            stream: Default::default(),
+            max_outbound_bytes: NETSERVER_MAX_OUTBOUND_BYTES,
+            outbound_policy: Default::default(),
+            require_handshake: false,
+            keepalive: Some(Default::default()),
+            reassembly: Default::default(),
+            control: false,
+            control_admins: Vec::new(),
+            max_connections: None,
+            read_chunk_size: NETSERVER_READ_CHUNK_SIZE,
+            events_capacity: NETSERVER_EVENTS_CAPACITY,
+        }
+    }
+}
+
+impl NetOutbound {
+    pub fn new(
+        max_size: usize,
+        policy: NetOutboundPolicy,
+    ) -> Self {
+        Self {
+            max_size,
+            policy,
+            ..Default::default()
+        }
+    }
+
+    // queues an encoded frame, applying the policy when it does not fit
+    pub fn push(
+        &mut self,
+        frame: Bytes,
+    ) -> Result<(), NetStreamErr> {
+        if self.size + frame.len() > self.max_size {
+            match self.policy {
+                // over the limit by at most the frame that got it there
+                NetOutboundPolicy::Block if self.size < self.max_size => {}
+                NetOutboundPolicy::Block => {
+                    self.dropped += 1;
+                    return Err(NetStreamErr::new(NetStreamErrorType::StreamBytesFull));
+                }
+                NetOutboundPolicy::DropOldest => {
+                    // partially written frame has to go out whole
+                    let first = self.partial as usize;
+                    while self.size + frame.len() > self.max_size && self.frames.len() > first {
+                        if let Some(dropped) = self.frames.remove(first) {
+                            self.size -= dropped.len();
+                            self.dropped += 1;
+                        }
+                    }
+                    if self.size + frame.len() > self.max_size {
+                        self.dropped += 1;
+                        return Err(NetStreamErr::new(NetStreamErrorType::StreamBytesFull));
+                    }
+                }
+                NetOutboundPolicy::Disconnect => {
+                    return Err(NetStreamErr::new(NetStreamErrorType::StreamBytesFull));
+                }
+            }
+        }
+        self.size += frame.len();
+        self.frames.push_back(frame);
+        Ok(())
+    }
+
+    // bytes to be written next
+    pub fn front(&self) -> Option<&Bytes> {
+        self.frames.front()
+    }
+
+    // marks n bytes of the front frame as written
+    pub fn advance(
+        &mut self,
+        n: usize,
+    ) {
+        if let Some(front) = self.frames.front_mut() {
+            let n = n.min(front.len());
+            front.advance(n);
+            self.size -= n;
+            self.partial = !front.is_empty();
+            if front.is_empty() {
+                self.frames.pop_front();
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.size = 0;
+        self.partial = false;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // queue is over the limit, the peer should not be read from
+    pub fn blocked(&self) -> bool {
+        self.policy == NetOutboundPolicy::Block && self.size > 0 && self.size >= self.max_size
+    }
+}
+
+impl NetConnectionShared {
+    pub fn new(
+        id: usize,
+        address: SocketAddr,
+        session_id: Uuid,
+    ) -> Self {
+        Self {
+            id,
+            address,
+            session_id,
+            paused: Default::default(),
+            wakeup: Default::default(),
+            frames_received: Default::default(),
+            bytes_received: Default::default(),
+            frames_sent: Default::default(),
+            bytes_sent: Default::default(),
+            frames_dropped: Default::default(),
+            bytes_skipped: Default::default(),
+            rtt_micros: Default::default(),
+        }
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn pause(
+        &self,
+        paused: bool,
+    ) {
+        self.paused.store(paused, Ordering::Relaxed);
+        self.wakeup.notify_one();
+    }
+
+    pub fn stats(&self) -> NetConnectionStats {
+        let rtt_micros = self.rtt_micros.load(Ordering::Relaxed);
+        NetConnectionStats {
+            id: self.id,
+            frames_received: self.frames_received.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            frames_sent: self.frames_sent.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            frames_dropped: self.frames_dropped.load(Ordering::Relaxed),
+            bytes_skipped: self.bytes_skipped.load(Ordering::Relaxed),
+            rtt_micros: (rtt_micros > 0).then_some(rtt_micros),
+        }
+    }
+
+    pub fn info(&self) -> NetConnectionInfo {
+        NetConnectionInfo {
+            id: self.id,
+            address: self.address,
+            session_id: self.session_id,
+            paused: self.paused(),
+        }
+    }
+}
+
+impl NetRegistry {
+    pub fn insert(
+        &self,
+        shared: Arc<NetConnectionShared>,
+    ) {
+        self.lock().insert(shared.id, shared);
+    }
+
+    pub fn remove(
+        &self,
+        id: usize,
+    ) {
+        self.lock().remove(&id);
+    }
+
+    pub fn get(
+        &self,
+        id: usize,
+    ) -> Option<Arc<NetConnectionShared>> {
+        self.lock().get(&id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<NetConnectionInfo> {
+        self.lock().values().map(|shared| shared.info()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    pub fn full(
+        &self,
+        max_connections: Option<usize>,
+    ) -> bool {
+        max_connections.is_some_and(|max| self.len() >= max)
+    }
+
+    // registry only holds plain data, a panic while holding the lock
+    // can not leave it inconsistent
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<usize, Arc<NetConnectionShared>>> {
+        self.connections
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl NetConnectionContext {
+    // queues the frame for sending
+    pub fn send(
+        &mut self,
+        frame: &NetFrame,
+    ) -> Result<(), NetStreamErr> {
+        let mut encoded =
+            BytesMut::zeroed(frame.encoded_len_delimited(self.max_frame_size, self.checksum));
+        frame.encode_into_delimited(
+            &mut encoded,
+            self.max_frame_size,
+            self.checksum,
+            self.delimiter,
+        )?;
+        let size = encoded.len() as u64;
+        let result = self.outbound.push(encoded.freeze());
+        self.shared
+            .frames_dropped
+            .store(self.outbound.dropped as u64, Ordering::Relaxed);
+        if result.is_ok() {
+            self.shared.frames_sent.fetch_add(1, Ordering::Relaxed);
+            self.shared.bytes_sent.fetch_add(size, Ordering::Relaxed);
+        }
+        if result.is_err() && self.outbound.policy == NetOutboundPolicy::Disconnect {
+            tracing::warn!("{}: disconnecting slow consumer", self.address);
+            self.outbound.clear();
+            self.closing = true;
+        }
+        result
+    }
+
+    // asks the server to drop the connection, queued frames are still sent
+    pub fn close(&mut self) {
+        self.closing = true;
+    }
+
+    // says Goodbye to the peer and closes the connection
+    pub fn goodbye(
+        &mut self,
+        reason: NetGoodbyeReason,
+    ) {
+        let frame = self.session.close(reason);
+        if let Err(err) = self.send(&frame) {
+            tracing::warn!("{}: goodbye not sent: {}", self.address, err);
+        }
+        self.close();
+    }
+
+    // asks the peer to drop what it has not handled yet
+    pub fn reset(&mut self) -> Result<(), NetStreamErr> {
+        self.send(&NetReset::Request.to_frame())?;
+        self.resetting = true;
+        Ok(())
+    }
+
+    // round trip times to the peer, None if keepalive is disabled
+    pub fn rtt(&self) -> Option<&NetRttStats> {
+        self.keepalive.as_ref().map(|keepalive| &keepalive.rtt)
+    }
+
+    // the peer should be read from
+    pub fn reading(&self) -> bool {
+        !self.closing && !self.outbound.blocked() && !self.shared.paused()
+    }
+
+    // nothing left to do for the connection
+    pub fn done(&self) -> bool {
+        self.closing && self.outbound.is_empty()
+    }
+}
+
+impl<H: ConnectionHandler> NetConnection<H> {
+    pub fn new(
+        id: usize,
+        address: SocketAddr,
+        config: &NetServerConfig,
+        registry: Arc<NetRegistry>,
+        handler: H,
+    ) -> Self {
+        let session_id = Uuid::new_v4();
+        let shared = Arc::new(NetConnectionShared::new(id, address, session_id));
+        registry.insert(shared.clone());
+        Self {
+            context: NetConnectionContext {
+                id,
+                address,
+                outbound: NetOutbound::new(config.max_outbound_bytes, config.outbound_policy),
+                checksum: config.stream.checksum,
+                delimiter: config.stream.delimiter,
+                max_frame_size: config.stream.max_frame_size,
+                session: NetSession::new(
+                    NetHello::with_config(&config.stream).with_session_id(session_id),
+                )
+                .required(config.require_handshake),
+                keepalive: config
+                    .keepalive
+                    .clone()
+                    .map(|keepalive| NetKeepalive::new(keepalive, Instant::now())),
+                resetting: false,
+                shared,
+                closing: false,
+            },
+            stream: NetStream::with_config(config.stream.clone()),
+            reassembler: NetReassembler::with_config(config.reassembly.clone()),
+            registry,
+            control: config.control,
+            admin: config.control_admins.contains(&address.ip()),
+            max_outbound_bytes: config.max_outbound_bytes,
+            keepalive: config.keepalive.clone(),
+            handler,
+        }
+    }
+
+    pub fn connected(&mut self) {
+        tracing::info!("accepted connection from: {}", self.context.address);
+        self.handler.on_connect(&mut self.context);
+    }
+
+    pub fn disconnected(&mut self) {
+        tracing::info!("connection closed: {}", self.context.address);
+        self.handler.on_disconnect(&mut self.context);
+        self.context.outbound.clear();
+        self.registry.remove(self.context.id);
+    }
+
+    pub fn failed(
+        &mut self,
+        err: NetStreamErr,
+    ) {
+        tracing::warn!("{}: {}", self.context.address, err);
+        self.handler.on_error(&mut self.context, err);
+    }
+
+    // writes the data to the stream, draining decoded frames as it goes so
+    // the stream never runs out of room. Corrupted data is skipped.
+    pub fn receive(
+        &mut self,
+        data: &[u8],
+    ) {
+        let shared = self.context.shared.clone();
+        shared
+            .bytes_received
+            .fetch_add(data.len() as u64, Ordering::Relaxed);
+        // resync happens here or, in NetStreamMode::Resync, in the stream
+        let skipped = self.stream.skipped;
+        let mut written = 0;
+        while written < data.len() && !self.context.closing {
+            let available = self.stream.available();
+            match self.stream.write(&data[written..]) {
+                Ok(accepted) => written += accepted,
+                Err(err) => {
+                    // protocol errors come from decoding, the data was taken in
+                    if self.stream.state == NetStreamState::Failure
+                        && err.category != NetStreamErrorType::StreamFailure
+                    {
+                        written += available.min(data.len() - written);
+                    }
+                    self.failed(err);
+                    self.stream.resync();
+                }
+            }
+            while let Ok(frame) = self.stream.next() {
+                if self.context.closing {
+                    break;
+                }
+                shared.frames_received.fetch_add(1, Ordering::Relaxed);
+                self.dispatch(frame);
+            }
+        }
+        let skipped = self.stream.skipped - skipped;
+        if skipped > 0 {
+            shared
+                .bytes_skipped
+                .fetch_add(skipped as u64, Ordering::Relaxed);
+            tracing::warn!("{}: skipped {} bytes", self.context.address, skipped);
+        }
+    }
+
+    // sends pings when due and drops expired partial messages, returns
+    // true if the peer timed out and already had an interval to take its
+    // Goodbye
+    pub fn tick(
+        &mut self,
+        now: Instant,
+    ) -> bool {
+        for message_id in self.reassembler.expire(now) {
+            tracing::warn!("{}: message {} expired", self.context.address, message_id);
+        }
+        let context = &mut self.context;
+        let Some(keepalive) = context.keepalive.as_mut() else {
+            return false;
+        };
+        // paused peers are not read, their Pongs would be missed
+        if context.shared.paused() && !context.closing {
+            keepalive.suspend(now);
+            return false;
+        }
+        match keepalive.tick(now) {
+            NetKeepaliveEvent::Idle => false,
+            NetKeepaliveEvent::Ping(ping) => {
+                if let Err(err) = context.send(&ping) {
+                    tracing::warn!("{}: ping not sent: {}", context.address, err);
+                }
+                false
+            }
+            NetKeepaliveEvent::Timeout if context.closing => true,
+            NetKeepaliveEvent::Timeout => {
+                tracing::warn!("{}: keepalive timeout", context.address);
+                context.goodbye(NetGoodbyeReason::Timeout);
+                false
+            }
+        }
+    }
+
+    // when tick() has to be called next
+    pub fn next_tick(&self) -> Option<Instant> {
+        self.context
+            .keepalive
+            .as_ref()
+            .map(|keepalive| keepalive.next_ping)
+            .into_iter()
+            .chain(self.reassembler.next_expiry())
+            .min()
+    }
+
+    // handshake frames are handled here, the rest goes to the handler
+    fn dispatch(
+        &mut self,
+        frame: NetFrame,
+    ) {
+        let context = &mut self.context;
+        match frame.kind() {
+            NetFrameTag::Hello => {
+                match context.session.accept(&frame) {
+                    Ok(_) => {
+                        tracing::info!(
+                            "{}: session {} established, version {:?}",
+                            context.address,
+                            context.session.session_id(),
+                            context.session.version()
+                        );
+                        let hello = context.session.hello();
+                        if let Err(err) = context.send(&hello) {
+                            tracing::warn!("{}: hello not sent: {}", context.address, err);
+                        }
+                    }
+                    Err(err) => {
+                        tracing::warn!("{}: handshake failed: {}", context.address, err);
+                        context.goodbye(err.rejection());
+                    }
+                }
+            }
+            NetFrameTag::Ping => {
+                if let Err(err) = context.send(&NetKeepalive::reply(&frame)) {
+                    tracing::warn!("{}: pong not sent: {}", context.address, err);
+                }
+            }
+            NetFrameTag::Pong => {
+                if let Some(keepalive) = context.keepalive.as_mut() {
+                    keepalive.pong(&frame, Instant::now());
+                    if let Some(smoothed) = keepalive.rtt.smoothed {
+                        context
+                            .shared
+                            .rtt_micros
+                            .store(smoothed.as_micros() as u64, Ordering::Relaxed);
+                    }
+                }
+            }
+            NetFrameTag::Reset => self.reset(&frame),
+            NetFrameTag::Goodbye => {
+                let reason = context.session.closed_by_peer(&frame);
+                tracing::info!("{}: goodbye, {:?}", context.address, reason);
+                context.close();
+            }
+            _ if !context.session.admits() => {
+                tracing::warn!("{}: {}", context.address, NetSessionError::HandshakeMissing);
+                context.goodbye(NetGoodbyeReason::ProtocolError);
+            }
+            NetFrameTag::Control if self.control => self.control(&frame),
+            NetFrameTag::MultiMessage => {
+                match self.reassembler.push(&frame) {
+                    Ok(Some(message)) => self.handler.on_message(context, message),
+                    Ok(None) => {}
+                    Err(err) => tracing::warn!("{}: fragment dropped: {}", context.address, err),
+                }
+            }
+            tag => self.handler.on_frame(context, tag, frame.data),
+        }
+    }
+
+    // answers control requests, replies from the peer are ignored
+    fn control(
+        &mut self,
+        frame: &NetFrame,
+    ) {
+        let reply = match NetControl::from_frame(frame) {
+            Ok(request) => self.control_request(request),
+            Err(err) => Some(NetControl::error(err)),
+        };
+        if let Some(reply) = reply {
+            if let Err(err) = self.context.send(&reply.to_frame()) {
+                tracing::warn!("{}: control reply not sent: {}", self.context.address, err);
+            }
+        }
+    }
+
+    fn control_request(
+        &mut self,
+        request: NetControl,
+    ) -> Option<NetControl> {
+        let context = &mut self.context;
+        tracing::debug!("{}: control {:?}", context.address, request);
+        if request.admin(context.id) && !self.admin {
+            tracing::warn!("{}: control {:?} denied", context.address, request);
+            return Some(NetControl::error("admin request not permitted"));
+        }
+        let reply = match request {
+            NetControl::SetOption(option) => {
+                match option {
+                    NetControlOption::MaxOutboundBytes(size) => {
+                        context.outbound.max_size = size.min(self.max_outbound_bytes);
+                    }
+                    NetControlOption::OutboundPolicy(policy) => context.outbound.policy = policy,
+                    NetControlOption::KeepaliveIntervalMillis(0) if self.keepalive.is_some() => {
+                        return Some(NetControl::error("keepalive is required"));
+                    }
+                    NetControlOption::KeepaliveIntervalMillis(0) => context.keepalive = None,
+                    // every interval means Pings to send, do not let peers flood
+                    NetControlOption::KeepaliveIntervalMillis(millis)
+                        if millis < NETSERVER_MIN_KEEPALIVE_MILLIS =>
+                    {
+                        return Some(NetControl::error(format!(
+                            "keepalive interval below {} ms",
+                            NETSERVER_MIN_KEEPALIVE_MILLIS
+                        )));
+                    }
+                    NetControlOption::KeepaliveIntervalMillis(millis) => {
+                        let interval = Duration::from_millis(millis);
+                        let config = match self.keepalive.as_ref() {
+                            Some(limit) => {
+                                NetKeepaliveConfig {
+                                    interval: interval.min(limit.interval),
+                                    max_missed: limit.max_missed,
+                                }
+                            }
+                            None => {
+                                NetKeepaliveConfig {
+                                    interval,
+                                    max_missed: NETSESSION_KEEPALIVE_MAX_MISSED,
+                                }
+                            }
+                        };
+                        context.keepalive = Some(NetKeepalive::new(config, Instant::now()));
+                    }
+                }
+                NetControl::Ok
+            }
+            NetControl::QueryStats {
+                id,
+            } => {
+                match id.map_or(Some(context.shared.clone()), |id| self.registry.get(id)) {
+                    Some(shared) => NetControl::Stats(shared.stats()),
+                    None => NetControl::error(format!("no connection {:?}", id)),
+                }
+            }
+            NetControl::SetLogLevel {
+                level,
+            } => {
+                match logger::core::set_level(&level) {
+                    Ok(()) => NetControl::Ok,
+                    Err(err) => NetControl::error(err),
+                }
+            }
+            NetControl::Pause {
+                id,
+            } if id == context.id => NetControl::error("connection can not pause itself"),
+            NetControl::Pause {
+                id,
+            }
+            | NetControl::Resume {
+                id,
+            } => {
+                match self.registry.get(id) {
+                    Some(shared) => {
+                        shared.pause(matches!(request, NetControl::Pause { .. }));
+                        NetControl::Ok
+                    }
+                    None => NetControl::error(format!("no connection {}", id)),
+                }
+            }
+            NetControl::ListConnections => {
+                NetControl::Connections {
+                    connections: self.registry.list(),
+                }
+            }
+            _ => return None,
+        };
+        Some(reply)
+    }
+
+    // Reset request drops what came before it and was not handled yet,
+    // which is the reassembly in flight. Frames already decoded behind
+    // it were sent after the Reset and are kept.
+    fn reset(
+        &mut self,
+        frame: &NetFrame,
+    ) {
+        let context = &mut self.context;
+        match NetReset::from_frame(frame) {
+            Ok(NetReset::Request) => {
+                tracing::info!("{}: reset by the peer", context.address);
+                self.reassembler.clear();
+                if let Err(err) = context.send(&NetReset::Ack.to_frame()) {
+                    tracing::warn!("{}: reset ack not sent: {}", context.address, err);
+                }
+                self.handler.on_reset(context);
+            }
+            Ok(NetReset::Ack) => context.resetting = false,
+            Err(err) => tracing::warn!("{}: {}", context.address, err),
+        }
+    }
+}
+
+impl<H: ConnectionHandler> NetStreamServer<H> {
+    pub async fn bind<F: FnMut() -> H + Send + 'static>(
+        address: SocketAddr,
+        factory: F,
+    ) -> io::Result<Self> {
+        Self::with_config(address, Default::default(), factory).await
+    }
+
+    pub async fn with_config<F: FnMut() -> H + Send + 'static>(
+        address: SocketAddr,
+        config: NetServerConfig,
+        factory: F,
+    ) -> io::Result<Self> {
+        // connections would not be able to decode a frame
+        config
+            .stream
+            .validate()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        Ok(Self {
+            listener: tokio::net::TcpListener::bind(address).await?,
+            next_id: 1,
+            config,
+            registry: Default::default(),
+            factory: Box::new(factory),
+            shutdown: Default::default(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn registry(&self) -> Arc<NetRegistry> {
+        self.registry.clone()
+    }
+
+    pub fn shutdown_handle(&self) -> NetServerShutdown {
+        self.shutdown.clone()
+    }
+
+    // accepts connections until shut down, then waits for the connection
+    // tasks to finish
+    // failing accept() does not stop the server
+    pub async fn run(mut self) -> io::Result<()> {
+        let mut tasks = JoinSet::new();
+        loop {
+            let accepted = tokio::select! {
+                _ = self.shutdown.token.cancelled() => break,
+                accepted = self.listener.accept() => accepted,
+            };
+            let (socket, address) = match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    tracing::warn!("accept failed: {}", err);
+                    if !connection_error(&err) {
+                        // give live connections a chance to free something up
+                        let backoff = Duration::from_millis(NETSERVER_ACCEPT_BACKOFF_MILLIS);
+                        tokio::select! {
+                            _ = self.shutdown.token.cancelled() => break,
+                            _ = tokio::time::sleep(backoff) => {}
+                        }
+                    }
+                    continue;
+                }
+            };
+            if self.registry.full(self.config.max_connections) {
+                tracing::warn!("connection limit reached, dropping: {}", address);
+                continue;
+            }
+            let connection = NetConnection::new(
+                self.next_id,
+                address,
+                &self.config,
+                self.registry.clone(),
+                (self.factory)(),
+            );
+            self.next_id += 1;
+            tasks.spawn(serve(
+                socket,
+                connection,
+                self.config.read_chunk_size,
+                self.shutdown.clone(),
+            ));
+            // reap finished connections so the set does not grow forever
+            while tasks.try_join_next().is_some() {}
+        }
+        self.shutdown.shutdown();
+        while tasks.join_next().await.is_some() {}
+        Ok(())
+    }
+}
+
+impl NetServerShutdown {
+    pub fn shutdown(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.token.is_cancelled()
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    if let Some(deadline) = deadline {
+        tokio::time::sleep_until(deadline.into()).await;
+    }
+}
+
+// drives a single connection until the peer closes it, the handler asks
+// to close it, or the server shuts down
+async fn serve<H: ConnectionHandler>(
+    mut socket: TcpStream,
+    mut connection: NetConnection<H>,
+    chunk_size: usize,
+    shutdown: NetServerShutdown,
+) {
+    connection.connected();
+    let linger = Duration::from_millis(NETSERVER_SHUTDOWN_LINGER_MILLIS);
+    let shared = connection.context.shared.clone();
+    let (mut reader, mut writer) = socket.split();
+    let mut chunk = vec![0; chunk_size.max(1)];
+    while !connection.context.done() {
+        let reading = connection.context.reading();
+        let pending = connection.context.outbound.front().cloned();
+        let next_tick = connection.next_tick();
+        // reads and writes are both cancel safe. Branches are polled in
+        // order, so pause and shutdown take effect before more data is
+        // read, and queued frames drain before the peer is read again.
+        tokio::select! {
+            biased;
+            _ = shutdown.token.cancelled(), if !connection.context.closing => {
+                connection.context.goodbye(NetGoodbyeReason::Shutdown);
+            },
+            // peer that does not take the Goodbye is not waited for
+            _ = tokio::time::sleep(linger), if shutdown.is_shutdown() => break,
+            // reading is switched on or off by the control channel
+            _ = shared.wakeup.notified() => {},
+            _ = sleep_until(next_tick), if next_tick.is_some() => {
+                if connection.tick(Instant::now()) {
+                    break;
+                }
+            },
+            written = writer.write(pending.as_deref().unwrap_or_default()), if pending.is_some() => {
+                match written {
+                    Ok(0) => {
+                        connection.failed(io::Error::from(io::ErrorKind::WriteZero).into());
+                        break;
+                    }
+                    Ok(n) => connection.context.outbound.advance(n),
+                    Err(err) => {
+                        connection.failed(err.into());
+                        break;
+                    }
+                }
+            },
+            read = reader.read(&mut chunk), if reading => match read {
+                // Reading 0 bytes means the other side has closed the
+                // connection or is done writing, then so are we.
+                Ok(0) => break,
+                Ok(n) => connection.receive(&chunk[..n]),
+                Err(err) => {
+                    connection.failed(err.into());
+                    break;
+                }
+            },
+        }
+    }
+    connection.disconnected();
+}
+
+#[cfg(feature = "mio")]
+impl<H: ConnectionHandler> NetServer<H> {
+    pub fn bind<F: FnMut() -> H + Send + 'static>(
+        address: SocketAddr,
+        factory: F,
+    ) -> io::Result<Self> {
+        Self::with_config(address, Default::default(), factory)
+    }
+
+    pub fn with_config<F: FnMut() -> H + Send + 'static>(
+        address: SocketAddr,
+        config: NetServerConfig,
+        factory: F,
+    ) -> io::Result<Self> {
+        config
+            .stream
+            .validate()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let poll = Poll::new()?;
+        let mut listener = TcpListener::bind(address)?;
+        // Register the server with poll we can receive events for it.
+        poll.registry()
+            .register(&mut listener, NETSERVER_LISTENER_TOKEN, Interest::READABLE)?;
+        Ok(Self {
+            poll,
+            events: Events::with_capacity(config.events_capacity.max(1)),
+            chunk: vec![0; config.read_chunk_size.max(1)],
+            listener,
+            connections: Default::default(),
+            next_token: Token(NETSERVER_LISTENER_TOKEN.0 + 1),
+            accept_retry: None,
+            config,
+            registry: Default::default(),
+            factory: Box::new(factory),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn registry(&self) -> Arc<NetRegistry> {
+        self.registry.clone()
+    }
+
+    pub fn run(&mut self) -> io::Result<()> {
+        loop {
+            self.poll_once(Some(Duration::from_millis(NETSERVER_TICK_MILLIS)))?;
+        }
+    }
+
+    // waits for socket events once and handles them
+    pub fn poll_once(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> io::Result<()> {
+        self.poll.poll(&mut self.events, timeout)?;
+        let events: Vec<(Token, bool, bool)> = self
+            .events
+            .iter()
+            .map(|event| (event.token(), event.is_readable(), event.is_writable()))
+            .collect();
+        for (token, readable, writable) in events {
+            match token {
+                NETSERVER_LISTENER_TOKEN => self.accept()?,
+                token => {
+                    // Maybe received an event for a TCP connection.
+                    let done = match self.connections.get_mut(&token) {
+                        Some(connection) => {
+                            connection.handle(
+                                self.poll.registry(),
+                                &mut self.chunk,
+                                readable,
+                                writable,
+                            )
+                        }
+                        // Sporadic events happen, we can safely ignore them.
+                        None => false,
+                    };
+                    if done {
+                        self.close(token)?;
+                    }
+                }
+            }
+        }
+        let now = Instant::now();
+        // the listener is edge triggered, pending connections would
+        // wait for the next one to arrive otherwise
+        if self.accept_retry.is_some_and(|retry| retry <= now) {
+            self.accept_retry = None;
+            self.accept()?;
+        }
+        self.tick(now)
+    }
+
+    // sends keepalive pings that are due, drops timed out connections and
+    // picks up connections paused or resumed by the control channel
+    fn tick(
+        &mut self,
+        now: Instant,
+    ) -> io::Result<()> {
+        let registry = self.poll.registry();
+        let done: Vec<Token> = self
+            .connections
+            .iter_mut()
+            .filter_map(|(token, connection)| {
+                let due = connection
+                    .connection
+                    .next_tick()
+                    .is_some_and(|tick| tick <= now);
+                let done = (due && connection.connection.tick(now))
+                    || connection.handle(registry, &mut self.chunk, false, false);
+                done.then_some(*token)
+            })
+            .collect();
+        for token in done {
+            self.close(token)?;
+        }
+        Ok(())
+    }
+
+    fn accept(&mut self) -> io::Result<()> {
+        loop {
+            // Received an event for the TCP server socket, which
+            // indicates we can accept an connection.
+            let (mut socket, address) = match self.listener.accept() {
+                Ok((socket, address)) => (socket, address),
+                // If we get a `WouldBlock` error we know our
+                // listener has no more incoming connections queued,
+                // so we can return to polling and wait for some
+                // more.
+                Err(ref err) if would_block(err) => return Ok(()),
+                Err(ref err) if connection_error(err) => {
+                    tracing::warn!("accept failed: {}", err);
+                    continue;
+                }
+                // out of descriptors or memory, retried after a pause
+                Err(err) => {
+                    tracing::warn!("accept failed: {}", err);
+                    let backoff = Duration::from_millis(NETSERVER_ACCEPT_BACKOFF_MILLIS);
+                    self.accept_retry = Some(Instant::now() + backoff);
+                    return Ok(());
+                }
+            };
+            if self.registry.full(self.config.max_connections) {
+                tracing::warn!("connection limit reached, dropping: {}", address);
+                continue;
+            }
+            let token = self.next_token;
+            self.next_token = Token(token.0 + 1);
+            self.poll
+                .registry()
+                .register(&mut socket, token, Interest::READABLE)?;
+            let mut connection = NetMioConnection {
+                socket,
+                interest: Some(Interest::READABLE),
+                connection: NetConnection::new(
+                    token.0,
+                    address,
+                    &self.config,
+                    self.registry.clone(),
+                    (self.factory)(),
+                ),
+            };
+            connection.connection.connected();
+            // frames sent on connect go out right away
+            let done = connection.handle(self.poll.registry(), &mut self.chunk, false, false);
+            self.connections.insert(token, connection);
+            if done {
+                self.close(token)?;
+            }
+        }
+    }
+
+    fn close(
+        &mut self,
+        token: Token,
+    ) -> io::Result<()> {
+        if let Some(mut connection) = self.connections.remove(&token) {
+            connection.connection.disconnected();
+            if connection.interest.is_some() {
+                self.poll.registry().deregister(&mut connection.socket)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "mio")]
+impl<H: ConnectionHandler> NetMioConnection<H> {
+    // handles socket readiness, returns true if the connection is done.
+    // Queued frames are flushed on every event, frames sent by the handler
+    // usually fit the socket buffer and do not have to wait for WRITABLE.
+    fn handle(
+        &mut self,
+        registry: &Registry,
+        chunk: &mut [u8],
+        readable: bool,
+        _writable: bool,
+    ) -> bool {
+        if readable {
+            match self.receive(chunk) {
+                Ok(false) => {}
+                Ok(true) => return true,
+                Err(err) => {
+                    self.connection.failed(err.into());
+                    return true;
+                }
+            }
+        }
+        if let Err(err) = self.flush() {
+            self.connection.failed(err.into());
+            return true;
+        }
+        if self.connection.context.done() {
+            return true;
+        }
+        if let Err(err) = self.update_interest(registry) {
+            self.connection.failed(err.into());
+            return true;
+        }
+        false
+    }
+
+    // WRITABLE only while frames are pending, READABLE unless the outbound
+    // queue blocks reading or the connection is paused. With neither the
+    // socket is deregistered, registering it again reports the data that
+    // arrived meanwhile.
+    fn update_interest(
+        &mut self,
+        registry: &Registry,
+    ) -> io::Result<()> {
+        let context = &self.connection.context;
+        let interest = match (context.reading(), context.outbound.is_empty()) {
+            (true, true) => Some(Interest::READABLE),
+            (true, false) => Some(Interest::READABLE | Interest::WRITABLE),
+            (false, false) => Some(Interest::WRITABLE),
+            (false, true) => None,
+        };
+        if interest == self.interest {
+            return Ok(());
+        }
+        let token = Token(context.id);
+        match (self.interest, interest) {
+            (_, None) => registry.deregister(&mut self.socket)?,
+            (None, Some(interest)) => registry.register(&mut self.socket, token, interest)?,
+            (Some(_), Some(interest)) => registry.reregister(&mut self.socket, token, interest)?,
+        }
+        self.interest = interest;
+        Ok(())
+    }
+
+    // reads everything available on the socket and frames it,
+    // returns true if the connection is done
+    fn receive(
+        &mut self,
+        chunk: &mut [u8],
+    ) -> io::Result<bool> {
+        while self.connection.context.reading() {
+            match self.socket.read(chunk) {
+                // Reading 0 bytes means the other side has closed the
+                // connection or is done writing, then so are we.
+                Ok(0) => return Ok(true),
+                Ok(n) => self.connection.receive(&chunk[..n]),
+                // Would block "errors" are the OS's way of saying that the
+                // connection is not actually ready to perform this I/O operation.
+                Err(ref err) if would_block(err) => return Ok(false),
+                Err(ref err) if interrupted(err) => continue,
+                // Other errors we'll consider fatal.
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(false)
+    }
+
+    // writes as much of the queued frames as the socket takes,
+    // the rest is written on the next writable event
+    fn flush(&mut self) -> io::Result<()> {
+        let outbound = &mut self.connection.context.outbound;
+        while let Some(pending) = outbound.front() {
+            match self.socket.write(pending) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => outbound.advance(n),
+                Err(ref err) if would_block(err) => break,
+                Err(ref err) if interrupted(err) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "mio")]
+fn would_block(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::WouldBlock
+}
+
+// accept() errors caused by the peer, the next connection is fine
+fn connection_error(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::Interrupted
+    )
+}
+
+#[cfg(feature = "mio")]
+fn interrupted(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::Interrupted
+}
@@ -0,0 +1,1165 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use tokio_util::codec::Framed;
+
+use crate::{
+    netclient::types::NetStreamClient,
+    netcodec::types::NetFrameCodec,
+    netcontrol::types::{NetControl, NetControlOption},
+    netfragment::types::NetFragmenter,
+    netframe::{
+        consts::NETFRAME_MAX_DATA_SIZE,
+        types::{NetFrame, NetFrameTag},
+    },
+    netserver::{
+        consts::NETSERVER_MIN_KEEPALIVE_MILLIS,
+        types::{
+            ConnectionHandler,
+            NetConnection,
+            NetConnectionContext,
+            NetOutboundPolicy,
+            NetServerConfig,
+            NetStreamServer,
+        },
+    },
+    netsession::{
+        consts::NETSESSION_MIN_PROTOCOL_VERSION,
+        error::NetSessionError,
+        types::{NetGoodbyeReason, NetKeepalive, NetKeepaliveConfig, NetReset, NetRttStats},
+    },
+    netstream::{
+        error::NetStreamErr,
+        types::{NetStream, NetStreamConfig, NetStreamMode},
+    },
+};
+
+#[derive(Debug, PartialEq)]
+pub enum Event {
+    Connect(usize),
+    Frame(usize, NetFrame),
+    Message(usize, Bytes),
+    Reset(usize),
+    Error(usize),
+    Disconnect(usize),
+}
+
+#[derive(Clone, Default)]
+pub struct Recorder {
+    pub events: Arc<Mutex<Vec<Event>>>,
+    pub echo: bool,
+    // round trip times seen when the connection closed
+    pub rtt: Arc<Mutex<Option<NetRttStats>>>,
+}
+
+impl Recorder {
+    pub fn frames(&self) -> Vec<NetFrame> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|event| {
+                match event {
+                    Event::Frame(_, frame) => Some(frame.clone()),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+impl ConnectionHandler for Recorder {
+    fn on_connect(
+        &mut self,
+        ctx: &mut NetConnectionContext,
+    ) {
+        self.events.lock().unwrap().push(Event::Connect(ctx.id));
+    }
+
+    fn on_frame(
+        &mut self,
+        ctx: &mut NetConnectionContext,
+        tag: NetFrameTag,
+        data: Bytes,
+    ) {
+        let frame = NetFrame::with_kind(tag, data);
+        if self.echo {
+            let _ = ctx.send(&frame);
+        }
+        self.events
+            .lock()
+            .unwrap()
+            .push(Event::Frame(ctx.id, frame));
+    }
+
+    fn on_message(
+        &mut self,
+        ctx: &mut NetConnectionContext,
+        data: Bytes,
+    ) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(Event::Message(ctx.id, data));
+    }
+
+    fn on_reset(
+        &mut self,
+        ctx: &mut NetConnectionContext,
+    ) {
+        self.events.lock().unwrap().push(Event::Reset(ctx.id));
+    }
+
+    fn on_error(
+        &mut self,
+        ctx: &mut NetConnectionContext,
+        _err: NetStreamErr,
+    ) {
+        self.events.lock().unwrap().push(Event::Error(ctx.id));
+    }
+
+    fn on_disconnect(
+        &mut self,
+        ctx: &mut NetConnectionContext,
+    ) {
+        *self.rtt.lock().unwrap() = ctx.rtt().cloned();
+        self.events.lock().unwrap().push(Event::Disconnect(ctx.id));
+    }
+}
+
+async fn wait_until(mut done: impl FnMut() -> bool) {
+    for _ in 0..100 {
+        if done() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_dispatches_frames() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let server = NetStreamServer::bind("127.0.0.1:0".parse().unwrap(), move || handler.clone())
+        .await
+        .unwrap();
+    let mut client = TcpStream::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    let frames: Vec<NetFrame> = (0..8u8)
+        .map(|i| NetFrame::with_kind(NetFrameTag::SingleMessage, vec![i; 1000]))
+        .collect();
+    // coalesced frames, split at arbitrary points
+    let buffer: Vec<u8> = frames
+        .iter()
+        .flat_map(|frame| frame.encode().unwrap())
+        .collect();
+    for chunk in buffer.chunks(777) {
+        client.write_all(chunk).await.unwrap();
+    }
+    wait_until(|| recorder.frames().len() == frames.len()).await;
+    assert_eq!(recorder.frames(), frames);
+    drop(client);
+    wait_until(|| {
+        recorder
+            .events
+            .lock()
+            .unwrap()
+            .contains(&Event::Disconnect(1))
+    })
+    .await;
+    {
+        let events = recorder.events.lock().unwrap();
+        assert_eq!(events.first(), Some(&Event::Connect(1)));
+        assert_eq!(events.last(), Some(&Event::Disconnect(1)));
+    }
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_handler_replies() {
+    let recorder = Recorder {
+        echo: true,
+        ..Default::default()
+    };
+    let handler = recorder.clone();
+    let server = NetStreamServer::bind("127.0.0.1:0".parse().unwrap(), move || handler.clone())
+        .await
+        .unwrap();
+    let mut client = TcpStream::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    let frame = NetFrame::with_kind(NetFrameTag::SingleMessage, vec![0x42; 100]);
+    let encoded = frame.encode().unwrap();
+    client.write_all(&encoded).await.unwrap();
+    let mut reply = vec![0; encoded.len()];
+    tokio::time::timeout(Duration::from_secs(5), client.read_exact(&mut reply))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(reply, encoded);
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_shutdown_closes_connections() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let server = NetStreamServer::bind("127.0.0.1:0".parse().unwrap(), move || handler.clone())
+        .await
+        .unwrap();
+    let mut client = TcpStream::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    wait_until(|| !recorder.events.lock().unwrap().is_empty()).await;
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+    assert_eq!(
+        *recorder.events.lock().unwrap(),
+        vec![Event::Connect(1), Event::Disconnect(1)]
+    );
+    // server says goodbye and closes its side of the socket
+    let mut buffer = Vec::new();
+    client.read_to_end(&mut buffer).await.unwrap();
+    assert_eq!(
+        buffer,
+        NetGoodbyeReason::Shutdown.to_frame().encode().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_disconnects_slow_consumer() {
+    let recorder = Recorder {
+        echo: true,
+        ..Default::default()
+    };
+    let handler = recorder.clone();
+    let config = NetServerConfig {
+        max_outbound_bytes: 64 * 1024,
+        outbound_policy: NetOutboundPolicy::Disconnect,
+        ..Default::default()
+    };
+    let server = NetStreamServer::with_config("127.0.0.1:0".parse().unwrap(), config, move || {
+        handler.clone()
+    })
+    .await
+    .unwrap();
+    let mut client = TcpStream::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    // client never reads the echoed frames
+    let encoded = NetFrame::with_kind(NetFrameTag::SingleMessage, vec![0x42; 60000])
+        .encode()
+        .unwrap();
+    let writing = tokio::spawn(async move {
+        for _ in 0..2000 {
+            if client.write_all(&encoded).await.is_err() {
+                break;
+            }
+        }
+        client
+    });
+    wait_until(|| {
+        recorder
+            .events
+            .lock()
+            .unwrap()
+            .contains(&Event::Disconnect(1))
+    })
+    .await;
+    assert!(
+        recorder
+            .events
+            .lock()
+            .unwrap()
+            .contains(&Event::Disconnect(1))
+    );
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+    drop(writing);
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_handshake_and_goodbye() {
+    let recorder = Recorder {
+        echo: true,
+        ..Default::default()
+    };
+    let handler = recorder.clone();
+    let config = NetServerConfig {
+        require_handshake: true,
+        ..Default::default()
+    };
+    let server = NetStreamServer::with_config("127.0.0.1:0".parse().unwrap(), config, move || {
+        handler.clone()
+    })
+    .await
+    .unwrap();
+    let mut client = NetStreamClient::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    let session = client.handshake().await.unwrap();
+    assert!(session.established());
+    assert!(!session.session_id().is_nil());
+    let frame = NetFrame::with_kind(NetFrameTag::SingleMessage, vec![0x42; 100]);
+    client.send_frame(frame.clone()).await.unwrap();
+    assert_eq!(client.next_frame().await, Some(Ok(frame.clone())));
+    client.goodbye(NetGoodbyeReason::Normal).await.unwrap();
+    assert_eq!(client.next_frame().await, None);
+    wait_until(|| {
+        recorder
+            .events
+            .lock()
+            .unwrap()
+            .contains(&Event::Disconnect(1))
+    })
+    .await;
+    // Hello and Goodbye are not passed to the handler
+    assert_eq!(recorder.frames(), vec![frame]);
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_failure_handshake_required() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let config = NetServerConfig {
+        require_handshake: true,
+        ..Default::default()
+    };
+    let server = NetStreamServer::with_config("127.0.0.1:0".parse().unwrap(), config, move || {
+        handler.clone()
+    })
+    .await
+    .unwrap();
+    let mut client = NetStreamClient::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    let frame = NetFrame::with_kind(NetFrameTag::SingleMessage, vec![0x42; 100]);
+    client.send_frame(frame).await.unwrap();
+    assert_eq!(
+        client.next_frame().await,
+        Some(Ok(NetGoodbyeReason::ProtocolError.to_frame()))
+    );
+    assert_eq!(client.next_frame().await, None);
+    assert!(recorder.frames().is_empty());
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_failure_incompatible_version() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let server = NetStreamServer::bind("127.0.0.1:0".parse().unwrap(), move || handler.clone())
+        .await
+        .unwrap();
+    let mut client = NetStreamClient::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    client.session.local.version = NETSESSION_MIN_PROTOCOL_VERSION - 1;
+    assert_eq!(
+        client.handshake().await.unwrap_err(),
+        NetSessionError::Closed(NetGoodbyeReason::IncompatibleVersion)
+    );
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_failure_incompatible_features() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    // server reads extended length frames, the client can not
+    let config = NetServerConfig {
+        stream: NetStreamConfig {
+            max_frame_size: 1024 * 1024,
+            max_buffered_bytes: 2 * 1024 * 1024,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let server = NetStreamServer::with_config("127.0.0.1:0".parse().unwrap(), config, move || {
+        handler.clone()
+    })
+    .await
+    .unwrap();
+    let mut client = NetStreamClient::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    assert_eq!(
+        client.handshake().await.unwrap_err(),
+        NetSessionError::Closed(NetGoodbyeReason::IncompatibleFeatures)
+    );
+    assert_eq!(client.next_frame().await, None);
+    assert!(recorder.frames().is_empty());
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_failure_stream_config() {
+    // the biggest frame does not fit the buffer
+    let config = NetServerConfig {
+        stream: NetStreamConfig {
+            max_frame_size: 1024,
+            max_buffered_bytes: 16,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let err = NetStreamServer::with_config("127.0.0.1:0".parse().unwrap(), config, || {
+        Recorder::default()
+    })
+    .await
+    .err()
+    .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+fn keepalive_config() -> NetServerConfig {
+    NetServerConfig {
+        keepalive: Some(NetKeepaliveConfig {
+            interval: Duration::from_millis(20),
+            max_missed: 2,
+        }),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_keepalive_rtt() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let server = NetStreamServer::with_config(
+        "127.0.0.1:0".parse().unwrap(),
+        keepalive_config(),
+        move || handler.clone(),
+    )
+    .await
+    .unwrap();
+    let mut client = NetStreamClient::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    // client answers the pings on its own while it reads, a few intervals
+    // go by without anything else arriving
+    let read = tokio::time::timeout(Duration::from_millis(100), client.next_frame()).await;
+    assert!(read.is_err());
+    client.goodbye(NetGoodbyeReason::Normal).await.unwrap();
+    wait_until(|| {
+        recorder
+            .events
+            .lock()
+            .unwrap()
+            .contains(&Event::Disconnect(1))
+    })
+    .await;
+    let rtt = recorder.rtt.lock().unwrap().clone().unwrap();
+    assert!(rtt.samples >= 2);
+    assert!(rtt.min <= rtt.max);
+    // pings are not passed to the handler
+    assert!(recorder.frames().is_empty());
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_keepalive_timeout() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let server = NetStreamServer::with_config(
+        "127.0.0.1:0".parse().unwrap(),
+        keepalive_config(),
+        move || handler.clone(),
+    )
+    .await
+    .unwrap();
+    // plain codec, unlike NetStreamClient it never answers
+    let socket = TcpStream::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let mut client = Framed::new(socket, NetFrameCodec::default());
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    // server gives up after max_missed pings
+    let mut frames = Vec::new();
+    while let Some(frame) = client.next().await {
+        frames.push(frame.unwrap());
+    }
+    let last = frames.pop().unwrap();
+    assert_eq!(last, NetGoodbyeReason::Timeout.to_frame());
+    assert_eq!(frames.len(), 2);
+    assert!(frames.iter().all(|frame| frame.kind() == NetFrameTag::Ping));
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_answers_ping() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let server = NetStreamServer::bind("127.0.0.1:0".parse().unwrap(), move || handler.clone())
+        .await
+        .unwrap();
+    let mut client = NetStreamClient::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    let ping = NetFrame::with_kind(NetFrameTag::Ping, vec![0, 0, 0, 7]);
+    client.send_frame(ping.clone()).await.unwrap();
+    assert_eq!(
+        client.next_frame().await,
+        Some(Ok(NetKeepalive::reply(&ping)))
+    );
+    assert!(recorder.frames().is_empty());
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_reassembles_messages() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let server = NetStreamServer::bind("127.0.0.1:0".parse().unwrap(), move || handler.clone())
+        .await
+        .unwrap();
+    let mut client = NetStreamClient::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    let mut fragmenter = NetFragmenter::new(100);
+    let first = fragmenter.split(&[0x01; 250]).unwrap();
+    let second = fragmenter.split(&[0x02; 150]).unwrap();
+    // fragments of both messages interleaved
+    let interleaved = [&first[0], &second[0], &first[1], &second[1], &first[2]];
+    for frame in interleaved {
+        client.send_frame(frame.clone()).await.unwrap();
+    }
+    wait_until(|| {
+        recorder
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|event| matches!(event, Event::Message(..)))
+            .count()
+            == 2
+    })
+    .await;
+    let events: Vec<Event> = recorder
+        .events
+        .lock()
+        .unwrap()
+        .drain(..)
+        .filter(|event| matches!(event, Event::Frame(..) | Event::Message(..)))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            Event::Message(1, Bytes::from(vec![0x02; 150])),
+            Event::Message(1, Bytes::from(vec![0x01; 250]))
+        ]
+    );
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_reset_drops_reassembly() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let server = NetStreamServer::bind("127.0.0.1:0".parse().unwrap(), move || handler.clone())
+        .await
+        .unwrap();
+    let mut client = NetStreamClient::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    let mut fragmenter = NetFragmenter::new(100);
+    // first message is cut short by the reset
+    let cut = fragmenter.split(&[0x01; 250]).unwrap();
+    client.send_frame(cut[0].clone()).await.unwrap();
+    client.send_frame(cut[1].clone()).await.unwrap();
+    assert_eq!(client.reset(Duration::from_secs(5)).await, Ok(vec![]));
+    // the rest of it is out of order now and dropped
+    client.send_frame(cut[2].clone()).await.unwrap();
+    let whole = fragmenter.split(&[0x02; 250]).unwrap();
+    for frame in whole {
+        client.send_frame(frame).await.unwrap();
+    }
+    wait_until(|| {
+        recorder
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|event| matches!(event, Event::Message(..)))
+    })
+    .await;
+    let events: Vec<Event> = recorder
+        .events
+        .lock()
+        .unwrap()
+        .drain(..)
+        .filter(|event| matches!(event, Event::Message(..) | Event::Reset(_)))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            Event::Reset(1),
+            Event::Message(1, Bytes::from(vec![0x02; 250]))
+        ]
+    );
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_reset_keeps_frames_behind_it() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let server = NetStreamServer::bind("127.0.0.1:0".parse().unwrap(), move || handler.clone())
+        .await
+        .unwrap();
+    let mut client = TcpStream::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    let cut = NetFragmenter::new(100).split(&[0x01; 250]).unwrap();
+    let after = NetFrame::with_kind(NetFrameTag::SingleMessage, vec![0x02; 10]);
+    // Reset and the frame sent after it arrive in one chunk
+    let buffer: Vec<u8> = [&cut[0], &NetReset::Request.to_frame(), &after]
+        .iter()
+        .flat_map(|frame| frame.encode().unwrap())
+        .collect();
+    client.write_all(&buffer).await.unwrap();
+    wait_until(|| !recorder.frames().is_empty()).await;
+    let events: Vec<Event> = recorder
+        .events
+        .lock()
+        .unwrap()
+        .drain(..)
+        .filter(|event| !matches!(event, Event::Connect(_)))
+        .collect();
+    assert_eq!(events, vec![Event::Reset(1), Event::Frame(1, after)]);
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+async fn control(
+    client: &mut NetStreamClient,
+    request: NetControl,
+) -> NetControl {
+    client.send_frame(request.to_frame()).await.unwrap();
+    let reply = client.next_frame().await.unwrap().unwrap();
+    NetControl::from_frame(&reply).unwrap()
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_control_channel() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let config = NetServerConfig {
+        control: true,
+        control_admins: vec!["127.0.0.1".parse().unwrap()],
+        keepalive: None,
+        ..Default::default()
+    };
+    let server = NetStreamServer::with_config("127.0.0.1:0".parse().unwrap(), config, move || {
+        handler.clone()
+    })
+    .await
+    .unwrap();
+    let address = server.local_addr().unwrap();
+    let registry = server.registry();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    let mut admin = NetStreamClient::connect(address).await.unwrap();
+    let mut peer = NetStreamClient::connect(address).await.unwrap();
+    wait_until(|| registry.len() == 2).await;
+    let connections = match control(&mut admin, NetControl::ListConnections).await {
+        NetControl::Connections {
+            connections,
+        } => connections,
+        reply => panic!("unexpected reply {:?}", reply),
+    };
+    assert_eq!(
+        connections.iter().map(|info| info.id).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(connections[1].address, peer.local_addr().unwrap());
+    // paused peer is not read from until resumed
+    assert_eq!(
+        control(
+            &mut admin,
+            NetControl::Pause {
+                id: 2
+            }
+        )
+        .await,
+        NetControl::Ok
+    );
+    let frame = NetFrame::with_kind(NetFrameTag::SingleMessage, vec![0x42; 10]);
+    peer.send_frame(frame.clone()).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(recorder.frames().is_empty());
+    assert_eq!(
+        control(
+            &mut admin,
+            NetControl::Resume {
+                id: 2
+            }
+        )
+        .await,
+        NetControl::Ok
+    );
+    wait_until(|| !recorder.frames().is_empty()).await;
+    assert_eq!(recorder.frames(), vec![frame]);
+    match control(
+        &mut admin,
+        NetControl::QueryStats {
+            id: Some(2),
+        },
+    )
+    .await
+    {
+        NetControl::Stats(stats) => {
+            assert_eq!(stats.id, 2);
+            assert_eq!(stats.frames_received, 1);
+            assert_eq!(stats.bytes_received, 14);
+        }
+        reply => panic!("unexpected reply {:?}", reply),
+    }
+    // control frames are not passed to the handler
+    assert_eq!(recorder.frames().len(), 1);
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_pause_with_keepalive() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let config = NetServerConfig {
+        control: true,
+        control_admins: vec!["127.0.0.1".parse().unwrap()],
+        keepalive: Some(NetKeepaliveConfig {
+            interval: Duration::from_millis(50),
+            max_missed: 2,
+        }),
+        ..Default::default()
+    };
+    let server = NetStreamServer::with_config("127.0.0.1:0".parse().unwrap(), config, move || {
+        handler.clone()
+    })
+    .await
+    .unwrap();
+    let address = server.local_addr().unwrap();
+    let registry = server.registry();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    let mut admin = NetStreamClient::connect(address).await.unwrap();
+    let mut peer = NetStreamClient::connect(address).await.unwrap();
+    wait_until(|| registry.len() == 2).await;
+    assert_eq!(
+        control(
+            &mut admin,
+            NetControl::Pause {
+                id: 2
+            }
+        )
+        .await,
+        NetControl::Ok
+    );
+    let frame = NetFrame::with_kind(NetFrameTag::SingleMessage, vec![0x42; 10]);
+    peer.send_frame(frame.clone()).await.unwrap();
+    // the peer answers every Ping it gets
+    let answering = tokio::spawn(async move { while peer.next_frame().await.is_some() {} });
+    // admin answers its Pings meanwhile, nothing else is sent to it
+    let idle = tokio::time::timeout(Duration::from_millis(600), admin.next_frame()).await;
+    assert!(idle.is_err());
+    // paused well past max_missed intervals, still connected
+    assert!(registry.get(2).is_some());
+    assert!(recorder.frames().is_empty());
+    assert_eq!(
+        control(
+            &mut admin,
+            NetControl::Resume {
+                id: 2
+            }
+        )
+        .await,
+        NetControl::Ok
+    );
+    wait_until(|| !recorder.frames().is_empty()).await;
+    assert_eq!(recorder.frames(), vec![frame]);
+    assert!(!answering.is_finished());
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_failure_control_requests() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let config = NetServerConfig {
+        control: true,
+        control_admins: vec!["127.0.0.1".parse().unwrap()],
+        ..Default::default()
+    };
+    let server = NetStreamServer::with_config("127.0.0.1:0".parse().unwrap(), config, move || {
+        handler.clone()
+    })
+    .await
+    .unwrap();
+    let mut client = NetStreamClient::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    for request in [
+        NetControl::Pause {
+            id: 1,
+        },
+        NetControl::Resume {
+            id: 42,
+        },
+        NetControl::QueryStats {
+            id: Some(42),
+        },
+    ] {
+        assert!(matches!(
+            control(&mut client, request).await,
+            NetControl::Error { .. }
+        ));
+    }
+    client
+        .send_frame(NetFrame::with_kind(NetFrameTag::Control, &b"nope"[..]))
+        .await
+        .unwrap();
+    let reply = client.next_frame().await.unwrap().unwrap();
+    assert!(matches!(
+        NetControl::from_frame(&reply),
+        Ok(NetControl::Error { .. })
+    ));
+    // keepalive is configured, it can not be disabled
+    assert!(matches!(
+        control(
+            &mut client,
+            NetControl::SetOption(NetControlOption::KeepaliveIntervalMillis(0))
+        )
+        .await,
+        NetControl::Error { .. }
+    ));
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+// feeds a control request to a connection and returns the reply
+fn control_connection(
+    connection: &mut NetConnection<Recorder>,
+    request: NetControl,
+) -> NetControl {
+    connection.receive(&request.to_frame().encode().unwrap());
+    let mut reply = BytesMut::from(&connection.context.outbound.front().unwrap()[..]);
+    connection.context.outbound.clear();
+    let frame = NetFrame::decode(&mut reply, NETFRAME_MAX_DATA_SIZE, Default::default());
+    NetControl::from_frame(&frame.unwrap().unwrap()).unwrap()
+}
+
+#[test]
+fn netstreamserver_failure_control_not_admin() {
+    let config = NetServerConfig {
+        control: true,
+        control_admins: vec!["10.0.0.1".parse().unwrap()],
+        ..Default::default()
+    };
+    let mut connection = NetConnection::new(
+        1,
+        "127.0.0.1:5000".parse().unwrap(),
+        &config,
+        Default::default(),
+        Recorder::default(),
+    );
+    for request in [
+        NetControl::ListConnections,
+        NetControl::SetLogLevel {
+            level: "trace".into(),
+        },
+        NetControl::Pause {
+            id: 2,
+        },
+        NetControl::Resume {
+            id: 2,
+        },
+        NetControl::QueryStats {
+            id: Some(2),
+        },
+    ] {
+        assert_eq!(
+            control_connection(&mut connection, request),
+            NetControl::error("admin request not permitted")
+        );
+    }
+    // its own stats are fine
+    assert!(matches!(
+        control_connection(
+            &mut connection,
+            NetControl::QueryStats {
+                id: Some(1),
+            }
+        ),
+        NetControl::Stats(_)
+    ));
+}
+
+#[test]
+fn netstreamserver_ok_control_options_clamped() {
+    let config = NetServerConfig {
+        control: true,
+        ..Default::default()
+    };
+    let mut connection = NetConnection::new(
+        1,
+        "127.0.0.1:5000".parse().unwrap(),
+        &config,
+        Default::default(),
+        Recorder::default(),
+    );
+    let options = [
+        NetControlOption::MaxOutboundBytes(usize::MAX),
+        NetControlOption::KeepaliveIntervalMillis(u64::MAX),
+    ];
+    for option in options {
+        assert_eq!(
+            control_connection(&mut connection, NetControl::SetOption(option)),
+            NetControl::Ok
+        );
+    }
+    let keepalive = config.keepalive.unwrap();
+    assert_eq!(
+        connection.context.outbound.max_size,
+        config.max_outbound_bytes
+    );
+    assert_eq!(
+        connection.context.keepalive.as_ref().unwrap().config,
+        keepalive
+    );
+    // tightening them works
+    let options = [
+        NetControlOption::MaxOutboundBytes(1024),
+        NetControlOption::KeepaliveIntervalMillis(NETSERVER_MIN_KEEPALIVE_MILLIS),
+    ];
+    for option in options {
+        assert_eq!(
+            control_connection(&mut connection, NetControl::SetOption(option)),
+            NetControl::Ok
+        );
+    }
+    assert_eq!(connection.context.outbound.max_size, 1024);
+    let tightened = NetKeepaliveConfig {
+        interval: Duration::from_millis(NETSERVER_MIN_KEEPALIVE_MILLIS),
+        ..keepalive
+    };
+    assert_eq!(
+        connection.context.keepalive.as_ref().unwrap().config,
+        tightened
+    );
+    // but not below the floor, the interval stays as it was
+    for millis in [1, NETSERVER_MIN_KEEPALIVE_MILLIS - 1] {
+        assert!(matches!(
+            control_connection(
+                &mut connection,
+                NetControl::SetOption(NetControlOption::KeepaliveIntervalMillis(millis))
+            ),
+            NetControl::Error { .. }
+        ));
+    }
+    assert_eq!(
+        connection.context.keepalive.as_ref().unwrap().config,
+        tightened
+    );
+}
+
+#[test]
+fn netstreamserver_failure_control_keepalive_floor() {
+    // no keepalive configured, peers still can not ask for a tiny interval
+    let config = NetServerConfig {
+        control: true,
+        keepalive: None,
+        ..Default::default()
+    };
+    let mut connection = NetConnection::new(
+        1,
+        "127.0.0.1:5000".parse().unwrap(),
+        &config,
+        Default::default(),
+        Recorder::default(),
+    );
+    assert!(matches!(
+        control_connection(
+            &mut connection,
+            NetControl::SetOption(NetControlOption::KeepaliveIntervalMillis(10))
+        ),
+        NetControl::Error { .. }
+    ));
+    assert!(connection.context.keepalive.is_none());
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_control_disabled() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let server = NetStreamServer::bind("127.0.0.1:0".parse().unwrap(), move || handler.clone())
+        .await
+        .unwrap();
+    let mut client = NetStreamClient::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    let frame = NetControl::ListConnections.to_frame();
+    client.send_frame(frame.clone()).await.unwrap();
+    wait_until(|| !recorder.frames().is_empty()).await;
+    assert_eq!(recorder.frames(), vec![frame]);
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_max_connections() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let config = NetServerConfig {
+        max_connections: Some(1),
+        ..Default::default()
+    };
+    let server = NetStreamServer::with_config("127.0.0.1:0".parse().unwrap(), config, move || {
+        handler.clone()
+    })
+    .await
+    .unwrap();
+    let address = server.local_addr().unwrap();
+    let registry = server.registry();
+    let shutdown = server.shutdown_handle();
+    let running = tokio::spawn(server.run());
+    let first = TcpStream::connect(address).await.unwrap();
+    wait_until(|| registry.len() == 1).await;
+    // over the limit, closed without reaching the handler
+    let mut second = TcpStream::connect(address).await.unwrap();
+    let mut buffer = [0; 16];
+    assert_eq!(second.read(&mut buffer).await.unwrap(), 0);
+    assert_eq!(registry.len(), 1);
+    // the slot is free again once the first one leaves
+    drop(first);
+    wait_until(|| registry.is_empty()).await;
+    let _third = TcpStream::connect(address).await.unwrap();
+    wait_until(|| registry.len() == 1).await;
+    assert_eq!(registry.len(), 1);
+    assert_eq!(
+        recorder
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|event| matches!(event, Event::Connect(_)))
+            .count(),
+        2
+    );
+    shutdown.shutdown();
+    running.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn netstreamserver_ok_skips_garbage() {
+    for mode in [NetStreamMode::Strict, NetStreamMode::Resync] {
+        let recorder = Recorder::default();
+        let handler = recorder.clone();
+        let config = NetServerConfig {
+            stream: NetStream::builder().mode(mode).build().unwrap().config,
+            ..Default::default()
+        };
+        let server =
+            NetStreamServer::with_config("127.0.0.1:0".parse().unwrap(), config, move || {
+                handler.clone()
+            })
+            .await
+            .unwrap();
+        let mut client = TcpStream::connect(server.local_addr().unwrap())
+            .await
+            .unwrap();
+        let registry = server.registry();
+        let shutdown = server.shutdown_handle();
+        let running = tokio::spawn(server.run());
+        let frame = NetFrame::with_kind(NetFrameTag::SingleMessage, "after");
+        let mut buffer = vec![0xAB, 0xCD, 0xEF];
+        buffer.extend(frame.encode().unwrap());
+        client.write_all(&buffer).await.unwrap();
+        wait_until(|| !recorder.frames().is_empty()).await;
+        assert_eq!(recorder.frames(), vec![frame]);
+        // counted once the whole read was handled, after dispatching
+        let shared = registry.get(1).unwrap();
+        wait_until(|| shared.stats().bytes_skipped > 0).await;
+        assert_eq!(shared.stats().bytes_skipped, 3);
+        // only strict streams report the error to the handler
+        let errors = recorder
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|event| matches!(event, Event::Error(_)))
+            .count();
+        assert_eq!(errors, usize::from(mode == NetStreamMode::Strict));
+        shutdown.shutdown();
+        running.await.unwrap().unwrap();
+    }
+}
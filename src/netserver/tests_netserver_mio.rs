@@ -0,0 +1,159 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use crate::{
+    netframe::types::{NetFrame, NetFrameTag},
+    netserver::{
+        tests_netserver::{Event, Recorder},
+        types::{ConnectionHandler, NetServer},
+    },
+};
+
+
+fn poll_until<H: ConnectionHandler>(
+    server: &mut NetServer<H>,
+    mut done: impl FnMut(&NetServer<H>) -> bool,
+) {
+    for _ in 0..100 {
+        if done(server) {
+            break;
+        }
+        server.poll_once(Some(Duration::from_millis(50))).unwrap();
+    }
+}
+
+#[test]
+fn netserver_ok_dispatches_frames() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let mut server =
+        NetServer::bind("127.0.0.1:0".parse().unwrap(), move || handler.clone()).unwrap();
+    let mut client = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    let frames: Vec<NetFrame> = (0..8u8)
+        .map(|i| NetFrame::with_kind(NetFrameTag::SingleMessage, vec![i; 1000]))
+        .collect();
+    // coalesced frames, split at arbitrary points
+    let buffer: Vec<u8> = frames
+        .iter()
+        .flat_map(|frame| frame.encode().unwrap())
+        .collect();
+    for chunk in buffer.chunks(777) {
+        client.write_all(chunk).unwrap();
+    }
+    poll_until(&mut server, |_| recorder.frames().len() == frames.len());
+    assert_eq!(server.connections.len(), 1);
+    assert_eq!(recorder.frames(), frames);
+    // closed connection is dropped
+    drop(client);
+    poll_until(&mut server, |server| server.connections.is_empty());
+    assert!(server.connections.is_empty());
+    let events = recorder.events.lock().unwrap();
+    assert_eq!(events.first(), Some(&Event::Connect(1)));
+    assert_eq!(events.last(), Some(&Event::Disconnect(1)));
+    assert!(
+        events[1..events.len() - 1]
+            .iter()
+            .all(|event| matches!(event, Event::Frame(1, _)))
+    );
+}
+
+#[test]
+fn netserver_ok_resyncs_after_garbage() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let mut server =
+        NetServer::bind("127.0.0.1:0".parse().unwrap(), move || handler.clone()).unwrap();
+    let mut client = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    let frame = NetFrame::with_kind(NetFrameTag::SingleMessage, vec![0xAB; 0x0101]);
+    client.write_all(&[0x11, 0x22, 0x33]).unwrap();
+    client.write_all(&frame.encode().unwrap()).unwrap();
+    poll_until(&mut server, |_| !recorder.frames().is_empty());
+    assert_eq!(recorder.frames(), vec![frame]);
+    assert!(recorder.events.lock().unwrap().contains(&Event::Error(1)));
+    assert_eq!(
+        server
+            .connections
+            .values()
+            .next()
+            .unwrap()
+            .connection
+            .stream
+            .skipped,
+        3
+    );
+}
+
+#[test]
+fn netserver_ok_handler_replies() {
+    let recorder = Recorder {
+        echo: true,
+        ..Default::default()
+    };
+    let handler = recorder.clone();
+    let mut server =
+        NetServer::bind("127.0.0.1:0".parse().unwrap(), move || handler.clone()).unwrap();
+    let mut client = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    let frame = NetFrame::with_kind(NetFrameTag::SingleMessage, vec![0x42; 100]);
+    let encoded = frame.encode().unwrap();
+    client.write_all(&encoded).unwrap();
+    poll_until(&mut server, |_| !recorder.frames().is_empty());
+    client
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let mut reply = vec![0; encoded.len()];
+    client.read_exact(&mut reply).unwrap();
+    assert_eq!(reply, encoded);
+}
+
+#[test]
+fn netserver_ok_paused_connection_is_deregistered() {
+    let recorder = Recorder::default();
+    let handler = recorder.clone();
+    let mut server =
+        NetServer::bind("127.0.0.1:0".parse().unwrap(), move || handler.clone()).unwrap();
+    let mut client = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    poll_until(&mut server, |server| server.connections.len() == 1);
+    let shared = server
+        .connections
+        .values()
+        .next()
+        .unwrap()
+        .connection
+        .context
+        .shared
+        .clone();
+    shared.pause(true);
+    server.poll_once(Some(Duration::from_millis(10))).unwrap();
+    // nothing to read or write, no events either
+    let connection = server.connections.values().next().unwrap();
+    assert_eq!(connection.interest, None);
+    let frame = NetFrame::with_kind(NetFrameTag::SingleMessage, vec![0x42; 10]);
+    client.write_all(&frame.encode().unwrap()).unwrap();
+    for _ in 0..3 {
+        server.poll_once(Some(Duration::from_millis(20))).unwrap();
+    }
+    assert!(recorder.frames().is_empty());
+    // registered again, the data that arrived meanwhile is read
+    shared.pause(false);
+    poll_until(&mut server, |_| !recorder.frames().is_empty());
+    assert_eq!(recorder.frames(), vec![frame]);
+}
@@ -0,0 +1,105 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use bytes::Bytes;
+
+use crate::{
+    netserver::types::{NetOutbound, NetOutboundPolicy},
+    netstream::error::NetStreamErrorType,
+};
+
+
+fn frame(size: usize) -> Bytes {
+    Bytes::from(vec![0xAA; size])
+}
+
+#[test]
+fn netoutbound_ok_partial_writes() {
+    let mut outbound = NetOutbound::new(100, NetOutboundPolicy::Block);
+    outbound.push(frame(10)).unwrap();
+    outbound.push(frame(20)).unwrap();
+    assert_eq!(outbound.size, 30);
+    outbound.advance(4);
+    assert!(outbound.partial);
+    assert_eq!(outbound.front().unwrap().len(), 6);
+    outbound.advance(6);
+    assert!(!outbound.partial);
+    assert_eq!(outbound.front().unwrap().len(), 20);
+    outbound.advance(20);
+    assert!(outbound.is_empty());
+    assert_eq!(outbound.size, 0);
+}
+
+#[test]
+fn netoutbound_ok_block_queues_and_blocks() {
+    let mut outbound = NetOutbound::new(25, NetOutboundPolicy::Block);
+    outbound.push(frame(10)).unwrap();
+    outbound.push(frame(10)).unwrap();
+    assert!(!outbound.blocked());
+    // queued, it only takes the queue over the limit
+    outbound.push(frame(10)).unwrap();
+    assert_eq!(outbound.frames.len(), 3);
+    assert!(outbound.blocked());
+    // nothing more until it drains
+    let err = outbound.push(frame(10)).unwrap_err();
+    assert_eq!(err.category, NetStreamErrorType::StreamBytesFull);
+    assert_eq!(outbound.frames.len(), 3);
+    assert_eq!(outbound.size, 30);
+    assert_eq!(outbound.dropped, 1);
+    outbound.advance(10);
+    assert!(!outbound.blocked());
+    outbound.push(frame(10)).unwrap();
+}
+
+#[test]
+fn netoutbound_ok_drop_oldest_keeps_partial_frame() {
+    let mut outbound = NetOutbound::new(25, NetOutboundPolicy::DropOldest);
+    outbound.push(Bytes::from_static(&[1; 10])).unwrap();
+    outbound.push(Bytes::from_static(&[2; 10])).unwrap();
+    outbound.advance(5);
+    outbound.push(Bytes::from_static(&[3; 10])).unwrap();
+    outbound.push(Bytes::from_static(&[4; 10])).unwrap();
+    let frames: Vec<Bytes> = outbound.frames.iter().cloned().collect();
+    assert_eq!(
+        frames,
+        vec![
+            Bytes::from_static(&[1; 5]),
+            Bytes::from_static(&[3; 10]),
+            Bytes::from_static(&[4; 10]),
+        ]
+    );
+    assert_eq!(outbound.dropped, 1);
+    assert!(!outbound.blocked());
+}
+
+#[test]
+fn netoutbound_err_drop_oldest_frame_too_big() {
+    let mut outbound = NetOutbound::new(25, NetOutboundPolicy::DropOldest);
+    outbound.push(frame(10)).unwrap();
+    let err = outbound.push(frame(30)).unwrap_err();
+    assert_eq!(err.category, NetStreamErrorType::StreamBytesFull);
+    assert!(outbound.is_empty());
+    assert_eq!(outbound.dropped, 2);
+}
+
+#[test]
+fn netoutbound_err_disconnect_when_full() {
+    let mut outbound = NetOutbound::new(25, NetOutboundPolicy::Disconnect);
+    outbound.push(frame(20)).unwrap();
+    let err = outbound.push(frame(10)).unwrap_err();
+    assert_eq!(err.category, NetStreamErrorType::StreamBytesFull);
+    assert_eq!(outbound.frames.len(), 1);
+}
@@ -0,0 +1,265 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+#[cfg(feature = "mio")]
+use std::{collections::HashMap, time::Instant};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicBool, AtomicU64},
+        Arc,
+        Mutex,
+    },
+};
+
+use bytes::Bytes;
+#[cfg(feature = "mio")]
+use mio::{
+    net::{TcpListener, TcpStream},
+    Events,
+    Interest,
+    Poll,
+    Token,
+};
+use serde_derive::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::{
+    netfragment::types::{NetReassembler, NetReassemblerConfig},
+    netframe::types::{NetFrameChecksum, NetFrameTag},
+    netsession::types::{NetKeepalive, NetKeepaliveConfig, NetSession},
+    netstream::{
+        error::NetStreamErr,
+        types::{NetStream, NetStreamConfig},
+    },
+};
+
+
+// application side of a connection, every connection gets its own handler
+// instance. Callbacks get the connection context, which can be used to
+// send frames back to the peer or to close the connection.
+pub trait ConnectionHandler: Send + 'static {
+    // connection accepted, nothing was received yet
+    fn on_connect(
+        &mut self,
+        _ctx: &mut NetConnectionContext,
+    ) {
+    }
+
+    // frame decoded from the connection, MultiMessage fragments are not
+    // passed here but reassembled for on_message()
+    fn on_frame(
+        &mut self,
+        ctx: &mut NetConnectionContext,
+        tag: NetFrameTag,
+        data: Bytes,
+    );
+
+    // MultiMessage fragments reassembled into a whole message, fragments
+    // of different messages may interleave. Broken or expired messages
+    // are logged and dropped.
+    fn on_message(
+        &mut self,
+        _ctx: &mut NetConnectionContext,
+        _data: Bytes,
+    ) {
+    }
+
+    // peer asked for a Reset, messages it was still sending fragments of
+    // were dropped and the acknowledgement is already queued
+    fn on_reset(
+        &mut self,
+        _ctx: &mut NetConnectionContext,
+    ) {
+    }
+
+    // protocol or socket error. Stream is resynchronized after protocol
+    // errors, call ctx.close() to drop the connection instead.
+    fn on_error(
+        &mut self,
+        _ctx: &mut NetConnectionContext,
+        _err: NetStreamErr,
+    ) {
+    }
+
+    // connection is closed, frames sent now are dropped
+    fn on_disconnect(
+        &mut self,
+        _ctx: &mut NetConnectionContext,
+    ) {
+    }
+}
+
+// what happens to frames sent to a peer that does not read fast enough
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetOutboundPolicy {
+    // the connection stops reading from the peer until the queue drains
+    // below the limit, frames sent meanwhile are refused with
+    // StreamBytesFull. The frame taking the queue over the limit is still
+    // queued, so frames larger than the limit go out one at a time.
+    #[default]
+    Block,
+    // oldest queued frames are dropped to make room
+    DropOldest,
+    // slow consumer is disconnected, queued frames are dropped
+    Disconnect,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetServerConfig {
+    pub stream: NetStreamConfig,
+    pub max_outbound_bytes: usize,
+    pub outbound_policy: NetOutboundPolicy,
+    // peers have to send Hello before any other frame
+    pub require_handshake: bool,
+    // None disables pings, peers are still answered with Pong
+    pub keepalive: Option<NetKeepaliveConfig>,
+    // limits for MultiMessage reassembly, per connection
+    pub reassembly: NetReassemblerConfig,
+    // Control frames are handled by the server instead of the handler
+    pub control: bool,
+    // peers allowed to send admin requests, the ones acting on the whole
+    // server or on other connections. Nobody if empty.
+    pub control_admins: Vec<IpAddr>,
+    // connections above the limit are dropped right after accepting
+    pub max_connections: Option<usize>,
+    // bytes read from a socket at once
+    pub read_chunk_size: usize,
+    // socket events handled per poll, mio backend only
+    pub events_capacity: usize,
+}
+
+// encoded frames waiting to be written to the socket, the first one
+// might be partially written already
+#[derive(Debug, Default)]
+pub struct NetOutbound {
+    pub frames: VecDeque<Bytes>,
+    pub size: usize,
+    pub max_size: usize,
+    pub policy: NetOutboundPolicy,
+    pub partial: bool,
+    pub dropped: usize,
+}
+
+// per connection state handed to the handler
+#[derive(Debug)]
+pub struct NetConnectionContext {
+    pub id: usize,
+    pub address: SocketAddr,
+    pub outbound: NetOutbound,
+    pub checksum: NetFrameChecksum,
+    pub delimiter: u8,
+    // frames sent use the extended header only if it is above
+    // NETFRAME_MAX_DATA_SIZE, same as the peer
+    pub max_frame_size: usize,
+    // Hello/Goodbye state, answered by the server before the handler
+    pub session: NetSession,
+    // ping schedule and round trip times, None if disabled
+    pub keepalive: Option<NetKeepalive>,
+    // Reset sent, waiting for the acknowledgement
+    pub resetting: bool,
+    pub shared: Arc<NetConnectionShared>,
+    pub closing: bool,
+}
+
+// connection state visible to the rest of the server, counters are
+// updated by the connection itself
+#[derive(Debug)]
+pub struct NetConnectionShared {
+    pub id: usize,
+    pub address: SocketAddr,
+    pub session_id: Uuid,
+    // set by Pause control messages of other connections
+    pub paused: AtomicBool,
+    // wakes the connection up after Pause/Resume
+    pub wakeup: Notify,
+    pub frames_received: AtomicU64,
+    pub bytes_received: AtomicU64,
+    pub frames_sent: AtomicU64,
+    pub bytes_sent: AtomicU64,
+    pub frames_dropped: AtomicU64,
+    pub bytes_skipped: AtomicU64,
+    // smoothed round trip time, 0 until the first Pong
+    pub rtt_micros: AtomicU64,
+}
+
+// connections of a server, used by the control channel
+#[derive(Debug, Default)]
+pub struct NetRegistry {
+    pub connections: Mutex<BTreeMap<usize, Arc<NetConnectionShared>>>,
+}
+
+// backend independent part of a connection, bytes read from the socket
+// are framed by its own NetStream and passed to the handler
+#[derive(Debug)]
+pub struct NetConnection<H: ConnectionHandler> {
+    pub stream: NetStream,
+    pub reassembler: NetReassembler,
+    pub registry: Arc<NetRegistry>,
+    pub control: bool,
+    // peer is on NetServerConfig::control_admins
+    pub admin: bool,
+    // configured limits, SetOption requests can only tighten them
+    pub max_outbound_bytes: usize,
+    pub keepalive: Option<NetKeepaliveConfig>,
+    pub context: NetConnectionContext,
+    pub handler: H,
+}
+
+// tokio based server, every connection is served by its own task
+pub struct NetStreamServer<H: ConnectionHandler> {
+    pub listener: tokio::net::TcpListener,
+    pub next_id: usize,
+    pub config: NetServerConfig,
+    pub registry: Arc<NetRegistry>,
+    pub factory: Box<dyn FnMut() -> H + Send>,
+    pub shutdown: NetServerShutdown,
+}
+
+// stops the server it was taken from, can be cloned and moved to other tasks
+#[derive(Debug, Default, Clone)]
+pub struct NetServerShutdown {
+    pub token: CancellationToken,
+}
+
+#[cfg(feature = "mio")]
+pub struct NetMioConnection<H: ConnectionHandler> {
+    pub socket: TcpStream,
+    // None while the socket is deregistered
+    pub interest: Option<Interest>,
+    pub connection: NetConnection<H>,
+}
+
+// mio based server, creates a handler for every accepted connection
+#[cfg(feature = "mio")]
+pub struct NetServer<H: ConnectionHandler> {
+    pub poll: Poll,
+    pub events: Events,
+    // read buffer shared by all connections
+    pub chunk: Vec<u8>,
+    pub listener: TcpListener,
+    pub connections: HashMap<Token, NetMioConnection<H>>,
+    pub next_token: Token,
+    // accept() failed for lack of resources, try again at
+    pub accept_retry: Option<Instant>,
+    pub config: NetServerConfig,
+    pub registry: Arc<NetRegistry>,
+    pub factory: Box<dyn FnMut() -> H + Send>,
+}
@@ -0,0 +1,40 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// version sent in Hello, peers accept versions in MIN..=current and talk
+// using the lower of the two
+pub const NETSESSION_PROTOCOL_VERSION: u16 = 1;
+pub const NETSESSION_MIN_PROTOCOL_VERSION: u16 = 1;
+// version (16bit), features (32bit), session id (128bit)
+pub const NETSESSION_HELLO_SIZE_BYTES: usize = 22;
+// reason (8bit)
+pub const NETSESSION_GOODBYE_SIZE_BYTES: usize = 1;
+// feature bits
+pub const NETSESSION_FEATURE_CRC32: u32 = 0x01;
+pub const NETSESSION_FEATURE_XXHASH32: u32 = 0x02;
+// 0x04 is reserved for compression, not implemented yet
+pub const NETSESSION_FEATURE_EXTENDED_LENGTH: u32 = 0x08;
+// features changing how frames look on the wire, both sides need the same
+pub const NETSESSION_FRAMING_FEATURES: u32 =
+    NETSESSION_FEATURE_CRC32 | NETSESSION_FEATURE_XXHASH32 | NETSESSION_FEATURE_EXTENDED_LENGTH;
+// sequence number (32bit), echoed back in Pong
+pub const NETSESSION_PING_SIZE_BYTES: usize = 4;
+// keepalive defaults
+pub const NETSESSION_KEEPALIVE_INTERVAL_SECS: u64 = 30;
+pub const NETSESSION_KEEPALIVE_MAX_MISSED: u32 = 3;
+// Reset payload, empty or 0x00 asks the peer to reset, 0x01 acknowledges
+pub const NETSESSION_RESET_REQUEST: u8 = 0x00;
+pub const NETSESSION_RESET_ACK: u8 = 0x01;
@@ -0,0 +1,383 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::time::{Duration, Instant};
+
+use bytes::{BufMut, BytesMut};
+use uuid::Uuid;
+
+use crate::{
+    netframe::types::{NetFrame, NetFrameChecksum, NetFrameTag},
+    netsession::{
+        consts::*,
+        error::NetSessionError,
+        types::{
+            NetGoodbyeReason,
+            NetHello,
+            NetKeepalive,
+            NetKeepaliveConfig,
+            NetKeepaliveEvent,
+            NetReset,
+            NetRttStats,
+            NetSession,
+            NetSessionState,
+        },
+    },
+    netstream::{consts::NETSTREAM_MAX_FRAME_SIZE, types::NetStreamConfig},
+};
+
+impl NetHello {
+    pub fn new(features: u32) -> Self {
+        Self {
+            version: NETSESSION_PROTOCOL_VERSION,
+            features,
+            session_id: Uuid::nil(),
+        }
+    }
+
+    // features the stream is configured for
+    pub fn with_config(config: &NetStreamConfig) -> Self {
+        let mut features = match config.checksum {
+            NetFrameChecksum::None => 0,
+            NetFrameChecksum::Crc32 => NETSESSION_FEATURE_CRC32,
+            NetFrameChecksum::XxHash32 => NETSESSION_FEATURE_XXHASH32,
+        };
+        if config.max_frame_size > NETSTREAM_MAX_FRAME_SIZE {
+            features |= NETSESSION_FEATURE_EXTENDED_LENGTH;
+        }
+        Self::new(features)
+    }
+
+    pub fn with_session_id(
+        mut self,
+        session_id: Uuid,
+    ) -> Self {
+        self.session_id = session_id;
+        self
+    }
+
+    pub fn read(data: &[u8]) -> Result<Self, NetSessionError> {
+        if data.len() < NETSESSION_HELLO_SIZE_BYTES {
+            return Err(NetSessionError::TooLittleData);
+        }
+        let mut session_id = [0; 16];
+        session_id.copy_from_slice(&data[6..NETSESSION_HELLO_SIZE_BYTES]);
+        Ok(Self {
+            version: u16::from_be_bytes([data[0], data[1]]),
+            features: u32::from_be_bytes([data[2], data[3], data[4], data[5]]),
+            session_id: Uuid::from_bytes(session_id),
+        })
+    }
+
+    pub fn from_frame(frame: &NetFrame) -> Result<Self, NetSessionError> {
+        if frame.kind() != NetFrameTag::Hello {
+            return Err(NetSessionError::NotHello);
+        }
+        Self::read(&frame.data)
+    }
+
+    pub fn to_frame(&self) -> NetFrame {
+        let mut data = BytesMut::with_capacity(NETSESSION_HELLO_SIZE_BYTES);
+        data.put_u16(self.version);
+        data.put_u32(self.features);
+        data.put_slice(self.session_id.as_bytes());
+        NetFrame::with_kind(NetFrameTag::Hello, data.freeze())
+    }
+
+    pub fn compatible(&self) -> bool {
+        self.version >= NETSESSION_MIN_PROTOCOL_VERSION
+    }
+}
+
+impl NetGoodbyeReason {
+    pub fn from_frame(frame: &NetFrame) -> Result<Self, NetSessionError> {
+        if frame.kind() != NetFrameTag::Goodbye {
+            return Err(NetSessionError::NotGoodbye);
+        }
+        match frame.data.first() {
+            Some(reason) => Ok((*reason).into()),
+            None => Err(NetSessionError::TooLittleData),
+        }
+    }
+
+    pub fn to_frame(self) -> NetFrame {
+        NetFrame::with_kind(NetFrameTag::Goodbye, vec![self.into()])
+    }
+}
+
+impl From<u8> for NetGoodbyeReason {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => NetGoodbyeReason::Normal,
+            1 => NetGoodbyeReason::Shutdown,
+            2 => NetGoodbyeReason::IncompatibleVersion,
+            3 => NetGoodbyeReason::ProtocolError,
+            4 => NetGoodbyeReason::Timeout,
+            5 => NetGoodbyeReason::IncompatibleFeatures,
+            value => NetGoodbyeReason::Unknown(value),
+        }
+    }
+}
+
+impl From<NetGoodbyeReason> for u8 {
+    fn from(value: NetGoodbyeReason) -> Self {
+        match value {
+            NetGoodbyeReason::Normal => 0,
+            NetGoodbyeReason::Shutdown => 1,
+            NetGoodbyeReason::IncompatibleVersion => 2,
+            NetGoodbyeReason::ProtocolError => 3,
+            NetGoodbyeReason::Timeout => 4,
+            NetGoodbyeReason::IncompatibleFeatures => 5,
+            NetGoodbyeReason::Unknown(value) => value,
+        }
+    }
+}
+
+impl NetReset {
+    pub fn from_frame(frame: &NetFrame) -> Result<Self, NetSessionError> {
+        if frame.kind() != NetFrameTag::Reset {
+            return Err(NetSessionError::NotReset);
+        }
+        match frame.data.first() {
+            Some(&NETSESSION_RESET_ACK) => Ok(NetReset::Ack),
+            _ => Ok(NetReset::Request),
+        }
+    }
+
+    pub fn to_frame(self) -> NetFrame {
+        let kind = match self {
+            NetReset::Request => NETSESSION_RESET_REQUEST,
+            NetReset::Ack => NETSESSION_RESET_ACK,
+        };
+        NetFrame::with_kind(NetFrameTag::Reset, vec![kind])
+    }
+}
+
+impl NetSession {
+    pub fn new(local: NetHello) -> Self {
+        Self {
+            local,
+            ..Default::default()
+        }
+    }
+
+    pub fn required(
+        mut self,
+        required: bool,
+    ) -> Self {
+        self.required = required;
+        self
+    }
+
+    // Hello to send to the peer
+    pub fn hello(&self) -> NetFrame {
+        self.local.to_frame()
+    }
+
+    // takes the peer Hello, on error the session should be closed with
+    // the reason returned by rejection()
+    pub fn accept(
+        &mut self,
+        frame: &NetFrame,
+    ) -> Result<&NetHello, NetSessionError> {
+        if self.state != NetSessionState::Handshake {
+            return Err(NetSessionError::UnexpectedHello);
+        }
+        let remote = NetHello::from_frame(frame)?;
+        if !remote.compatible() {
+            return Err(NetSessionError::IncompatibleVersion(remote.version));
+        }
+        // frames would be read wrong by one of the sides
+        if (remote.features ^ self.local.features) & NETSESSION_FRAMING_FEATURES != 0 {
+            return Err(NetSessionError::IncompatibleFeatures(remote.features));
+        }
+        self.state = NetSessionState::Established;
+        Ok(self.remote.insert(remote))
+    }
+
+    // frame other than Hello/Goodbye can be passed on
+    pub fn admits(&self) -> bool {
+        match self.state {
+            NetSessionState::Handshake => !self.required,
+            NetSessionState::Established => true,
+            NetSessionState::Closed => false,
+        }
+    }
+
+    // closes the session, returns the Goodbye to send
+    pub fn close(
+        &mut self,
+        reason: NetGoodbyeReason,
+    ) -> NetFrame {
+        self.state = NetSessionState::Closed;
+        self.goodbye = Some(reason);
+        reason.to_frame()
+    }
+
+    // takes the peer Goodbye, unreadable reason counts as Normal
+    pub fn closed_by_peer(
+        &mut self,
+        frame: &NetFrame,
+    ) -> NetGoodbyeReason {
+        let reason = NetGoodbyeReason::from_frame(frame).unwrap_or(NetGoodbyeReason::Normal);
+        self.state = NetSessionState::Closed;
+        self.goodbye = Some(reason);
+        reason
+    }
+
+    pub fn established(&self) -> bool {
+        self.state == NetSessionState::Established
+    }
+
+    // protocol version both sides speak
+    pub fn version(&self) -> Option<u16> {
+        self.remote
+            .as_ref()
+            .map(|remote| remote.version.min(self.local.version))
+    }
+
+    // features supported by both sides
+    pub fn features(&self) -> u32 {
+        self.remote
+            .as_ref()
+            .map_or(0, |remote| remote.features & self.local.features)
+    }
+
+    // session id assigned by the server
+    pub fn session_id(&self) -> Uuid {
+        match &self.remote {
+            Some(remote) if !remote.session_id.is_nil() => remote.session_id,
+            _ => self.local.session_id,
+        }
+    }
+}
+
+impl NetSessionError {
+    // Goodbye reason to close the session with after the error
+    pub fn rejection(&self) -> NetGoodbyeReason {
+        match self {
+            NetSessionError::IncompatibleVersion(_) => NetGoodbyeReason::IncompatibleVersion,
+            NetSessionError::IncompatibleFeatures(_) => NetGoodbyeReason::IncompatibleFeatures,
+            _ => NetGoodbyeReason::ProtocolError,
+        }
+    }
+}
+
+impl Default for NetKeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(NETSESSION_KEEPALIVE_INTERVAL_SECS),
+            max_missed: NETSESSION_KEEPALIVE_MAX_MISSED,
+        }
+    }
+}
+
+impl NetRttStats {
+    pub fn add(
+        &mut self,
+        sample: Duration,
+    ) {
+        self.last = Some(sample);
+        self.min = Some(self.min.map_or(sample, |min| min.min(sample)));
+        self.max = Some(self.max.map_or(sample, |max| max.max(sample)));
+        self.smoothed = Some(
+            self.smoothed
+                .map_or(sample, |smoothed| (smoothed * 7 + sample) / 8),
+        );
+        self.samples += 1;
+    }
+}
+
+impl NetKeepalive {
+    // first ping goes out one interval after now
+    pub fn new(
+        config: NetKeepaliveConfig,
+        now: Instant,
+    ) -> Self {
+        Self {
+            next_ping: now + config.interval,
+            config,
+            next_sequence: 0,
+            outstanding: None,
+            missed: 0,
+            rtt: Default::default(),
+        }
+    }
+
+    // call at least once per interval, tells what to do next
+    pub fn tick(
+        &mut self,
+        now: Instant,
+    ) -> NetKeepaliveEvent {
+        if now < self.next_ping {
+            return NetKeepaliveEvent::Idle;
+        }
+        self.next_ping = now + self.config.interval;
+        if self.outstanding.is_some() {
+            self.missed += 1;
+            if self.missed >= self.config.max_missed {
+                return NetKeepaliveEvent::Timeout;
+            }
+        }
+        let sequence = self.next_sequence;
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        self.outstanding = Some((sequence, now));
+        NetKeepaliveEvent::Ping(NetFrame::with_kind(
+            NetFrameTag::Ping,
+            sequence.to_be_bytes().to_vec(),
+        ))
+    }
+
+    // takes the peer Pong, returns the round trip time if it answers
+    // the ping in flight
+    pub fn pong(
+        &mut self,
+        frame: &NetFrame,
+        now: Instant,
+    ) -> Option<Duration> {
+        let sequence: [u8; NETSESSION_PING_SIZE_BYTES] = frame
+            .data
+            .get(..NETSESSION_PING_SIZE_BYTES)?
+            .try_into()
+            .ok()?;
+        match self.outstanding {
+            Some((outstanding, sent)) if outstanding == u32::from_be_bytes(sequence) => {
+                let rtt = now.saturating_duration_since(sent);
+                self.outstanding = None;
+                self.missed = 0;
+                self.rtt.add(rtt);
+                Some(rtt)
+            }
+            _ => None,
+        }
+    }
+
+    // holds the pings back while the peer's Pongs are not read, nothing
+    // counts as missed and the schedule starts over one interval after now
+    pub fn suspend(
+        &mut self,
+        now: Instant,
+    ) {
+        self.next_ping = now + self.config.interval;
+        self.outstanding = None;
+        self.missed = 0;
+    }
+
+    // Pong answering the peer Ping
+    pub fn reply(ping: &NetFrame) -> NetFrame {
+        NetFrame::with_kind(NetFrameTag::Pong, ping.data.clone())
+    }
+}
@@ -0,0 +1,60 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use thiserror::Error;
+
+use crate::{netsession::types::NetGoodbyeReason, netstream::error::NetStreamErr};
+
+
+#[derive(Error, Debug, PartialEq, Default)]
+pub enum NetSessionError {
+    #[default]
+    #[error("Undefined session error")]
+    Undefined,
+
+    #[error("frame is not tagged as Hello")]
+    NotHello,
+
+    #[error("frame is not tagged as Goodbye")]
+    NotGoodbye,
+
+    #[error("frame is not tagged as Reset")]
+    NotReset,
+
+    #[error("Not enough data to read the handshake")]
+    TooLittleData,
+
+    #[error("protocol version {0} is not supported")]
+    IncompatibleVersion(u16),
+
+    #[error("peer features {0:#x} frame data differently")]
+    IncompatibleFeatures(u32),
+
+    #[error("Hello received on an established session")]
+    UnexpectedHello,
+
+    #[error("frame received before the handshake")]
+    HandshakeMissing,
+
+    #[error("Reset was not acknowledged in time")]
+    ResetTimeout,
+
+    #[error("session closed by the peer: {0:?}")]
+    Closed(NetGoodbyeReason),
+
+    #[error(transparent)]
+    Stream(#[from] NetStreamErr),
+}
@@ -0,0 +1,128 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+
+use crate::{
+    netframe::types::{NetFrame, NetFrameTag},
+    netsession::types::{NetKeepalive, NetKeepaliveConfig, NetKeepaliveEvent, NetRttStats},
+};
+
+
+fn config() -> NetKeepaliveConfig {
+    NetKeepaliveConfig {
+        interval: Duration::from_secs(1),
+        max_missed: 2,
+    }
+}
+
+fn ping(event: NetKeepaliveEvent) -> NetFrame {
+    match event {
+        NetKeepaliveEvent::Ping(frame) => frame,
+        event => panic!("expected ping, got {:?}", event),
+    }
+}
+
+#[test]
+fn keepalive_ok_ping_and_rtt() {
+    let start = Instant::now();
+    let mut keepalive = NetKeepalive::new(config(), start);
+    assert_eq!(keepalive.tick(start), NetKeepaliveEvent::Idle);
+    let sent = start + Duration::from_secs(1);
+    let frame = ping(keepalive.tick(sent));
+    assert_eq!(frame.kind(), NetFrameTag::Ping);
+    assert_eq!(frame.data, Bytes::from_static(&[0, 0, 0, 0]));
+    assert_eq!(keepalive.tick(sent), NetKeepaliveEvent::Idle);
+    let pong = NetKeepalive::reply(&frame);
+    assert_eq!(pong.kind(), NetFrameTag::Pong);
+    assert_eq!(
+        keepalive.pong(&pong, sent + Duration::from_millis(20)),
+        Some(Duration::from_millis(20))
+    );
+    // answered already
+    assert_eq!(
+        keepalive.pong(&pong, sent + Duration::from_millis(30)),
+        None
+    );
+    assert_eq!(keepalive.rtt.samples, 1);
+    // next ping carries the next sequence number
+    let frame = ping(keepalive.tick(sent + Duration::from_secs(1)));
+    assert_eq!(frame.data, Bytes::from_static(&[0, 0, 0, 1]));
+}
+
+#[test]
+fn keepalive_failure_missed_pongs() {
+    let start = Instant::now();
+    let mut keepalive = NetKeepalive::new(config(), start);
+    let stale = ping(keepalive.tick(start + Duration::from_secs(1)));
+    ping(keepalive.tick(start + Duration::from_secs(2)));
+    assert_eq!(keepalive.missed, 1);
+    // pong for an older ping does not count
+    assert_eq!(
+        keepalive.pong(&NetKeepalive::reply(&stale), start + Duration::from_secs(2)),
+        None
+    );
+    assert_eq!(
+        keepalive.tick(start + Duration::from_secs(3)),
+        NetKeepaliveEvent::Timeout
+    );
+}
+
+#[test]
+fn keepalive_ok_pong_resets_missed() {
+    let start = Instant::now();
+    let mut keepalive = NetKeepalive::new(config(), start);
+    ping(keepalive.tick(start + Duration::from_secs(1)));
+    let frame = ping(keepalive.tick(start + Duration::from_secs(2)));
+    assert_eq!(keepalive.missed, 1);
+    keepalive.pong(&NetKeepalive::reply(&frame), start + Duration::from_secs(2));
+    assert_eq!(keepalive.missed, 0);
+    ping(keepalive.tick(start + Duration::from_secs(3)));
+}
+
+#[test]
+fn keepalive_ok_suspend_forgets_missed() {
+    let start = Instant::now();
+    let mut keepalive = NetKeepalive::new(config(), start);
+    ping(keepalive.tick(start + Duration::from_secs(1)));
+    ping(keepalive.tick(start + Duration::from_secs(2)));
+    assert_eq!(keepalive.missed, 1);
+    keepalive.suspend(start + Duration::from_secs(3));
+    assert_eq!(keepalive.missed, 0);
+    assert_eq!(
+        keepalive.tick(start + Duration::from_secs(3)),
+        NetKeepaliveEvent::Idle
+    );
+    // schedule starts over, the next ping is not a miss
+    ping(keepalive.tick(start + Duration::from_secs(4)));
+    assert_eq!(keepalive.missed, 0);
+}
+
+#[test]
+fn keepalive_ok_rtt_stats() {
+    let mut rtt = NetRttStats::default();
+    for millis in [80, 160, 40] {
+        rtt.add(Duration::from_millis(millis));
+    }
+    assert_eq!(rtt.last, Some(Duration::from_millis(40)));
+    assert_eq!(rtt.min, Some(Duration::from_millis(40)));
+    assert_eq!(rtt.max, Some(Duration::from_millis(160)));
+    // 80 -> 90 -> 83.75
+    assert_eq!(rtt.smoothed, Some(Duration::from_micros(83750)));
+    assert_eq!(rtt.samples, 3);
+}
@@ -0,0 +1,161 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use bytes::Bytes;
+use uuid::Uuid;
+
+use crate::{
+    netframe::types::{NetFrame, NetFrameChecksum, NetFrameTag},
+    netsession::{
+        consts::*,
+        error::NetSessionError,
+        types::{NetGoodbyeReason, NetHello, NetReset, NetSession, NetSessionState},
+    },
+    netstream::types::NetStreamConfig,
+};
+
+
+#[test]
+fn session_ok_hello_roundtrip() {
+    let hello = NetHello::new(NETSESSION_FEATURE_CRC32 | NETSESSION_FEATURE_EXTENDED_LENGTH)
+        .with_session_id(Uuid::from_u128(0x0102_0304));
+    let frame = hello.to_frame();
+    assert_eq!(frame.kind(), NetFrameTag::Hello);
+    assert_eq!(frame.data.len(), NETSESSION_HELLO_SIZE_BYTES);
+    assert_eq!(&frame.data[..6], &[0x00, 0x01, 0x00, 0x00, 0x00, 0x09]);
+    assert_eq!(NetHello::from_frame(&frame), Ok(hello));
+}
+
+#[test]
+fn session_ok_hello_features_from_config() {
+    let config = NetStreamConfig {
+        checksum: NetFrameChecksum::XxHash32,
+        max_frame_size: 1 << 20,
+        ..Default::default()
+    };
+    assert_eq!(
+        NetHello::with_config(&config).features,
+        NETSESSION_FEATURE_XXHASH32 | NETSESSION_FEATURE_EXTENDED_LENGTH
+    );
+    assert_eq!(NetHello::with_config(&Default::default()).features, 0);
+}
+
+#[test]
+fn session_ok_handshake() {
+    let session_id = Uuid::from_u128(42);
+    let mut server =
+        NetSession::new(NetHello::new(NETSESSION_FEATURE_CRC32).with_session_id(session_id))
+            .required(true);
+    let mut client = NetSession::new(NetHello::new(NETSESSION_FEATURE_CRC32));
+    assert!(!server.admits());
+    server.accept(&client.hello()).unwrap();
+    client.accept(&server.hello()).unwrap();
+    assert!(server.established() && client.established());
+    assert!(server.admits());
+    assert_eq!(server.features(), NETSESSION_FEATURE_CRC32);
+    assert_eq!(client.features(), NETSESSION_FEATURE_CRC32);
+    assert_eq!(client.version(), Some(NETSESSION_PROTOCOL_VERSION));
+    assert_eq!(client.session_id(), session_id);
+    assert_eq!(server.session_id(), session_id);
+    assert_eq!(
+        server.accept(&client.hello()),
+        Err(NetSessionError::UnexpectedHello)
+    );
+}
+
+#[test]
+fn session_failure_incompatible_version() {
+    let mut server = NetSession::new(NetHello::new(0));
+    let hello = NetHello {
+        version: NETSESSION_MIN_PROTOCOL_VERSION - 1,
+        ..NetHello::new(0)
+    };
+    let err = server.accept(&hello.to_frame()).unwrap_err();
+    assert_eq!(
+        err,
+        NetSessionError::IncompatibleVersion(NETSESSION_MIN_PROTOCOL_VERSION - 1)
+    );
+    assert_eq!(err.rejection(), NetGoodbyeReason::IncompatibleVersion);
+    assert_eq!(server.state, NetSessionState::Handshake);
+}
+
+#[test]
+fn session_failure_incompatible_features() {
+    let mut server = NetSession::new(NetHello::new(NETSESSION_FEATURE_CRC32));
+    for features in [
+        0,
+        NETSESSION_FEATURE_XXHASH32,
+        NETSESSION_FEATURE_CRC32 | NETSESSION_FEATURE_EXTENDED_LENGTH,
+    ] {
+        let err = server
+            .accept(&NetHello::new(features).to_frame())
+            .unwrap_err();
+        assert_eq!(err, NetSessionError::IncompatibleFeatures(features));
+        assert_eq!(err.rejection(), NetGoodbyeReason::IncompatibleFeatures);
+        assert_eq!(server.state, NetSessionState::Handshake);
+    }
+    assert_eq!(u8::from(NetGoodbyeReason::IncompatibleFeatures), 5);
+    assert_eq!(
+        NetGoodbyeReason::from(5),
+        NetGoodbyeReason::IncompatibleFeatures
+    );
+}
+
+#[test]
+fn session_failure_short_hello() {
+    let mut server = NetSession::new(NetHello::new(0));
+    let frame = NetFrame::with_kind(NetFrameTag::Hello, Bytes::from_static(&[0x00, 0x01]));
+    assert_eq!(server.accept(&frame), Err(NetSessionError::TooLittleData));
+    let frame = NetFrame::with_kind(NetFrameTag::SingleMessage, NetHello::new(0).to_frame().data);
+    assert_eq!(server.accept(&frame), Err(NetSessionError::NotHello));
+}
+
+#[test]
+fn session_ok_goodbye() {
+    let mut server = NetSession::new(NetHello::new(0));
+    let mut client = NetSession::new(NetHello::new(0));
+    let frame = client.close(NetGoodbyeReason::Normal);
+    assert_eq!(frame.data, Bytes::from_static(&[0x00]));
+    assert_eq!(server.closed_by_peer(&frame), NetGoodbyeReason::Normal);
+    assert_eq!(server.state, NetSessionState::Closed);
+    assert!(!server.admits());
+    // unknown reasons are kept, trailing bytes are ignored
+    let frame = NetFrame::with_kind(NetFrameTag::Goodbye, Bytes::from_static(&[0x42, 0xFF]));
+    assert_eq!(
+        NetGoodbyeReason::from_frame(&frame),
+        Ok(NetGoodbyeReason::Unknown(0x42))
+    );
+    assert_eq!(u8::from(NetGoodbyeReason::Unknown(0x42)), 0x42);
+}
+
+#[test]
+fn session_ok_reset_frames() {
+    let request = NetReset::Request.to_frame();
+    assert_eq!(request.kind(), NetFrameTag::Reset);
+    assert_eq!(
+        request.data,
+        Bytes::from_static(&[NETSESSION_RESET_REQUEST])
+    );
+    assert_eq!(NetReset::from_frame(&request), Ok(NetReset::Request));
+    let ack = NetReset::Ack.to_frame();
+    assert_eq!(ack.data, Bytes::from_static(&[NETSESSION_RESET_ACK]));
+    assert_eq!(NetReset::from_frame(&ack), Ok(NetReset::Ack));
+    // empty Reset is a request
+    let empty = NetFrame::with_kind(NetFrameTag::Reset, Bytes::new());
+    assert_eq!(NetReset::from_frame(&empty), Ok(NetReset::Request));
+    let other = NetFrame::with_kind(NetFrameTag::Ping, Bytes::new());
+    assert_eq!(NetReset::from_frame(&other), Err(NetSessionError::NotReset));
+}
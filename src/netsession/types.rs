@@ -0,0 +1,157 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+// Hello is the first frame sent by both sides, client sends it right
+// after connecting and the server answers with its own:
+// ┌─────────┬──────────┬────────────┐
+// │  16bit  │  32bit   │   128bit   │
+// │         │          │            │
+// │ version │ features │ session id │
+// └─────────┴──────────┴────────────┘
+//
+// * version is the protocol version of the sender
+// * features is a bitmask of NETSESSION_FEATURE_* supported by the sender,
+//   session uses the features supported by both sides. Checksum and extended
+//   length bits describe the frames the sender reads and writes, they have to
+//   be the same on both sides
+// * session id is assigned by the server, client sends a nil uuid
+// * all fields are in network byte order
+//
+// Goodbye is sent before closing the connection on purpose:
+// ┌────────┐
+// │  8bit  │
+// │        │
+// │ reason │
+// └────────┘
+//
+// * bytes after the reason are ignored, so it can be extended later
+//
+// Peer sending an incompatible version gets a Goodbye with the
+// IncompatibleVersion reason and is disconnected, one framing its frames
+// differently gets IncompatibleFeatures.
+//
+// Ping carries a sequence number, Pong echoes the Ping data back as is:
+// ┌──────────┐
+// │  32bit   │
+// │          │
+// │ sequence │
+// └──────────┘
+//
+// * only one Ping is in flight, every interval without its Pong counts as
+//   missed and the peer is dropped after max_missed of them
+
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use crate::netframe::types::NetFrame;
+
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NetHello {
+    pub version: u16,
+    pub features: u32,
+    pub session_id: Uuid,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NetGoodbyeReason {
+    // peer is done
+    Normal,
+
+    // server is going down
+    Shutdown,
+
+    // protocol versions do not overlap
+    IncompatibleVersion,
+
+    // peers frame their frames differently, i.e. checksum modes differ
+    IncompatibleFeatures,
+
+    // peer broke the protocol, i.e. skipped the handshake
+    ProtocolError,
+
+    // peer stopped responding
+    Timeout,
+
+    // reason byte not assigned to anything (yet)
+    Unknown(u8),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NetReset {
+    Request,
+    Ack,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum NetSessionState {
+    #[default]
+    Handshake,
+    Established,
+    Closed,
+}
+
+// handshake state of a connection, does no io on its own
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NetSession {
+    pub local: NetHello,
+    pub remote: Option<NetHello>,
+    pub state: NetSessionState,
+    // reason the session was closed with, by either side
+    pub goodbye: Option<NetGoodbyeReason>,
+    // frames other than Hello are rejected until the handshake is done
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetKeepaliveConfig {
+    pub interval: Duration,
+    pub max_missed: u32,
+}
+
+// round trip times measured with Ping/Pong
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NetRttStats {
+    pub last: Option<Duration>,
+    pub min: Option<Duration>,
+    pub max: Option<Duration>,
+    // smoothed the same way tcp does it, 7/8 old + 1/8 new
+    pub smoothed: Option<Duration>,
+    pub samples: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum NetKeepaliveEvent {
+    // nothing to do until next_ping
+    Idle,
+    // Ping to send to the peer
+    Ping(NetFrame),
+    // too many pings went unanswered
+    Timeout,
+}
+
+// keepalive state of a connection, driven by tick(), does no io on its own
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetKeepalive {
+    pub config: NetKeepaliveConfig,
+    pub next_ping: Instant,
+    pub next_sequence: u32,
+    // sequence and send time of the ping waiting for its pong
+    pub outstanding: Option<(u32, Instant)>,
+    pub missed: u32,
+    pub rtt: NetRttStats,
+}
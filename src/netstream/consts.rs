@@ -14,6 +14,9 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+use crate::netframe::consts::NETFRAME_MAX_DATA_SIZE;
+
 pub const NETSTREAM_INTERNAL_CAPACITY: usize = 3 * u16::MAX as usize;
-// const NETSTREAM_MAX_FRAME_SIZE: usize = u16::MAX as usize;
+// extended frames are rejected unless the stream is configured otherwise
+pub const NETSTREAM_MAX_FRAME_SIZE: usize = NETFRAME_MAX_DATA_SIZE;
 pub const NETSTREAM_EXTERNAL_CAPACITY: usize = 256;
@@ -1,20 +1,218 @@
 use std::collections::VecDeque;
 
+use bytes::{Buf, BytesMut};
+
 use crate::{
-    netframe::{consts::NETFRAME_HEADER_SIZE_BYTES, types::NetFrame},
+    netframe::{
+        consts::{
+            NETFRAME_DELIMITER,
+            NETFRAME_EXTENDED_HEADER_SIZE_BYTES,
+            NETFRAME_EXTENDED_MAX_DATA_SIZE,
+            NETFRAME_HEADER_SIZE_BYTES,
+        },
+        types::{NetFrame, NetFrameChecksum},
+    },
     netstream::{
         consts::*,
         error::{NetStreamErr, NetStreamErrorType},
-        types::{FramingStream, NetStream, NetStreamState},
+        types::{
+            FramingStream,
+            NetStream,
+            NetStreamBuilder,
+            NetStreamConfig,
+            NetStreamMode,
+            NetStreamState,
+        },
     },
 };
 
+impl Default for NetStreamConfig {
+    fn default() -> Self {
+        Self {
+            max_buffered_bytes: NETSTREAM_INTERNAL_CAPACITY,
+            max_queued_frames: NETSTREAM_EXTERNAL_CAPACITY,
+            max_frame_size: NETSTREAM_MAX_FRAME_SIZE,
+            checksum: NetFrameChecksum::None,
+            delimiter: NETFRAME_DELIMITER,
+            mode: NetStreamMode::Strict,
+        }
+    }
+}
+
+impl NetStreamConfig {
+    // buffer size needed for the biggest frame, header and trailer included
+    pub fn min_buffered_bytes(&self) -> usize {
+        let max_frame_size = self.max_frame_size.min(NETFRAME_EXTENDED_MAX_DATA_SIZE);
+        let header_size = if NetFrame::extended(max_frame_size) {
+            NETFRAME_EXTENDED_HEADER_SIZE_BYTES
+        } else {
+            NETFRAME_HEADER_SIZE_BYTES
+        };
+        max_frame_size + header_size + self.checksum.size()
+    }
+
+    // rejects limits that leave the stream unable to decode anything
+    pub fn validate(&self) -> Result<(), NetStreamErr> {
+        let invalid = self.max_frame_size == 0
+            || self.max_frame_size > NETFRAME_EXTENDED_MAX_DATA_SIZE
+            || self.max_buffered_bytes < self.min_buffered_bytes()
+            || self.max_queued_frames == 0;
+        if invalid {
+            return Err(NetStreamErr::new(NetStreamErrorType::StreamConfigInvalid));
+        }
+        Ok(())
+    }
+}
+
 impl NetStream {
     pub fn new() -> Self {
+        Self::with_config(NetStreamConfig::default())
+    }
+
+    pub fn builder() -> NetStreamBuilder {
+        NetStreamBuilder::default()
+    }
+
+    // stream accepting frames up to max_frame_size bytes of data,
+    // extended length frames are accepted above NETFRAME_MAX_DATA_SIZE
+    pub fn with_max_frame_size(max_frame_size: usize) -> Self {
+        Self::with_config(NetStreamConfig {
+            max_frame_size,
+            ..Default::default()
+        })
+    }
+
+    // stream expecting the checksum trailer on every frame
+    pub fn with_checksum(checksum: NetFrameChecksum) -> Self {
+        Self::with_config(NetStreamConfig {
+            checksum,
+            ..Default::default()
+        })
+    }
+
+    // limits are adjusted instead of validated, so the biggest frame fits
+    // into the buffer. Outside of the crate NetStreamBuilder::build() is
+    // the way in, it rejects them instead.
+    pub(crate) fn with_config(mut config: NetStreamConfig) -> Self {
+        config.max_frame_size = config.max_frame_size.min(NETFRAME_EXTENDED_MAX_DATA_SIZE);
+        config.max_buffered_bytes = config.max_buffered_bytes.max(config.min_buffered_bytes());
         Self {
-            frames: VecDeque::with_capacity(NETSTREAM_EXTERNAL_CAPACITY),
-            buffer: Vec::with_capacity(NETSTREAM_INTERNAL_CAPACITY),
+            frames: VecDeque::with_capacity(
+                config.max_queued_frames.min(NETSTREAM_EXTERNAL_CAPACITY),
+            ),
+            buffer: BytesMut::with_capacity(
+                config.max_buffered_bytes.min(NETSTREAM_INTERNAL_CAPACITY),
+            ),
             state: NetStreamState::Empty,
+            skipped: 0,
+            config,
+        }
+    }
+
+    // number of bytes the next write can take
+    pub fn available(&self) -> usize {
+        self.config.max_buffered_bytes - self.buffer.len()
+    }
+
+    // extracts every complete frame held in the buffer, so only a partial
+    // frame (if any) is left behind for the next write.
+    // Stops early when the frame queue is full.
+    // Frame data is split off the front of the buffer without copying.
+    fn decode(&mut self) -> Result<(), NetStreamErr> {
+        let result = loop {
+            if self.frames.len() >= self.config.max_queued_frames {
+                break Ok(());
+            }
+
+            match NetFrame::decode_delimited(
+                &mut self.buffer,
+                self.config.max_frame_size,
+                self.config.checksum,
+                self.config.delimiter,
+            ) {
+                Ok(Some(frame)) => self.frames.push_back(frame),
+                // header or data is not complete yet, wait for more
+                Ok(None) => break Ok(()),
+                Err(err) => break Err(err.into()),
+            }
+        };
+
+        // protocol errors leave the garbage at the front of the buffer,
+        // stream stays unusable until reset() or resync()
+        self.state = if result.is_err() {
+            NetStreamState::Failure
+        } else if self.buffer.is_empty() {
+            NetStreamState::Empty
+        } else {
+            NetStreamState::InProgress
+        };
+
+        result
+    }
+}
+
+impl NetStreamBuilder {
+    pub fn max_buffered_bytes(
+        mut self,
+        max_buffered_bytes: usize,
+    ) -> Self {
+        self.config.max_buffered_bytes = max_buffered_bytes;
+        self
+    }
+
+    pub fn max_queued_frames(
+        mut self,
+        max_queued_frames: usize,
+    ) -> Self {
+        self.config.max_queued_frames = max_queued_frames;
+        self
+    }
+
+    pub fn max_frame_size(
+        mut self,
+        max_frame_size: usize,
+    ) -> Self {
+        self.config.max_frame_size = max_frame_size;
+        self
+    }
+
+    pub fn delimiter(
+        mut self,
+        delimiter: u8,
+    ) -> Self {
+        self.config.delimiter = delimiter;
+        self
+    }
+
+    pub fn mode(
+        mut self,
+        mode: NetStreamMode,
+    ) -> Self {
+        self.config.mode = mode;
+        self
+    }
+
+    // NetFrameChecksum::None turns the trailer off
+    pub fn checksum(
+        mut self,
+        checksum: NetFrameChecksum,
+    ) -> Self {
+        self.config.checksum = checksum;
+        self
+    }
+
+    // fails with StreamConfigInvalid if the limits leave the stream
+    // unable to decode anything, see NetStreamConfig::validate()
+    pub fn build(self) -> Result<NetStream, NetStreamErr> {
+        self.config.validate()?;
+        Ok(NetStream::with_config(self.config))
+    }
+}
+
+impl From<NetStreamConfig> for NetStreamBuilder {
+    fn from(config: NetStreamConfig) -> Self {
+        Self {
+            config,
         }
     }
 }
@@ -27,130 +225,89 @@ impl FramingStream for NetStream {
             });
         }
 
-        self.frames.pop_front().ok_or(NetStreamErr {
+        let frame = self.frames.pop_front().ok_or(NetStreamErr {
             category: NetStreamErrorType::StreamFailure,
-        })
+        })?;
+
+        // there is room in the queue again, pick up frames that were held back
+        // in the buffer. Errors are reported by the next write.
+        if self.decode().is_err() && self.config.mode == NetStreamMode::Resync {
+            self.resync();
+        }
+
+        Ok(frame)
     }
 
     fn write(
         &mut self,
-        data: Vec<u8>,
-    ) -> Result<(), NetStreamErr> {
-        // we do not have enough data to even ask for metadata
-        if data.len() + self.buffer.len() < NETFRAME_HEADER_SIZE_BYTES {
-            self.buffer.extend(data);
+        data: &[u8],
+    ) -> Result<usize, NetStreamErr> {
+        if self.state == NetStreamState::Failure {
+            return Err(NetStreamErr {
+                category: NetStreamErrorType::StreamFailure,
+            });
+        }
 
-            self.state = NetStreamState::InProgress;
+        if data.is_empty() {
+            return Ok(0);
+        }
 
-            return Ok(());
+        // take only as much as fits, the rest has to be written again later
+        let accepted = data.len().min(self.available());
+        if accepted == 0 {
+            // buffer is full, either because frames are held back by the full
+            // queue (drain it with next()), or because the frame is too big
+            let category = if self.frames.len() >= self.config.max_queued_frames {
+                NetStreamErrorType::StreamMessageCountFull
+            } else {
+                NetStreamErrorType::StreamBytesFull
+            };
+            return Err(NetStreamErr {
+                category,
+            });
         }
 
-        match self.state {
-            NetStreamState::Empty => {
-                match NetFrame::get_metadata(&data) {
-                    Ok(metadata) => {
-                        match data
-                            .len()
-                            .cmp(&(metadata.size as usize + NETFRAME_HEADER_SIZE_BYTES))
-                        {
-                            std::cmp::Ordering::Equal => {
-                                let frame: NetFrame = NetFrame {
-                                    tag: metadata.tag,
-                                    data: data[NETFRAME_HEADER_SIZE_BYTES..].to_vec(),
-                                };
-                                self.frames.push_back(frame);
-                                Ok(())
-                            }
-                            std::cmp::Ordering::Less => {
-                                self.buffer.extend(data);
-                                self.state = NetStreamState::InProgress;
-
-                                Ok(())
-                            }
-                            std::cmp::Ordering::Greater => {
-                                let frame: NetFrame = NetFrame {
-                                    tag: metadata.tag,
-                                    data: data[NETFRAME_HEADER_SIZE_BYTES
-                                        ..NETFRAME_HEADER_SIZE_BYTES + (metadata.size as usize)]
-                                        .to_vec(),
-                                };
-
-                                self.frames.push_back(frame);
-
-                                let remain = data
-                                    [NETFRAME_HEADER_SIZE_BYTES + (metadata.size as usize)..]
-                                    .to_vec();
-
-                                self.buffer = remain;
-
-                                self.state = NetStreamState::InProgress;
-
-                                Ok(())
-                            }
-                        }
-                    }
-                    Err(err) => Err(err.into()),
-                }
-            }
-            NetStreamState::InProgress => {
-                self.buffer.extend(data);
-
-                match NetFrame::get_metadata(&self.buffer) {
-                    Ok(metadata) => {
-                        match self
-                            .buffer
-                            .len()
-                            .cmp(&(metadata.size as usize + NETFRAME_HEADER_SIZE_BYTES))
-                        {
-                            std::cmp::Ordering::Equal => {
-                                let frame: NetFrame = NetFrame {
-                                    tag: metadata.tag,
-                                    data: self.buffer[NETFRAME_HEADER_SIZE_BYTES..].to_vec(),
-                                };
-
-                                self.frames.push_back(frame);
-
-                                self.buffer.clear();
-
-                                self.state = NetStreamState::Empty;
-
-                                Ok(())
-                            }
-                            std::cmp::Ordering::Less => {
-                                self.state = NetStreamState::InProgress;
-
-                                Ok(())
-                            }
-                            std::cmp::Ordering::Greater => {
-                                let frame: NetFrame = NetFrame {
-                                    tag: metadata.tag,
-                                    data: self.buffer[NETFRAME_HEADER_SIZE_BYTES
-                                        ..NETFRAME_HEADER_SIZE_BYTES + (metadata.size as usize)]
-                                        .to_vec(),
-                                };
-
-                                let remain = self.buffer
-                                    [NETFRAME_HEADER_SIZE_BYTES + (metadata.size as usize)..]
-                                    .to_vec();
-
-                                self.frames.push_back(frame);
-
-                                self.buffer = remain;
-
-                                self.state = NetStreamState::InProgress;
-
-                                Ok(())
-                            }
-                        }
-                    }
-                    Err(err) => Err(err.into()),
+        self.buffer.extend_from_slice(&data[..accepted]);
+
+        if let Err(err) = self.decode() {
+            match self.config.mode {
+                NetStreamMode::Strict => return Err(err),
+                NetStreamMode::Resync => {
+                    self.resync();
                 }
             }
-            NetStreamState::Failure => {
-                Err(NetStreamErr {
-                    category: NetStreamErrorType::StreamFailure,
-                })
-            }
         }
+
+        Ok(accepted)
+    }
+
+    fn reset(&mut self) {
+        self.frames.clear();
+        self.buffer.clear();
+        self.state = NetStreamState::Empty;
+    }
+
+    fn resync(&mut self) -> usize {
+        let mut skipped = 0;
+
+        while self.state == NetStreamState::Failure {
+            // drop the byte that failed to decode and everything up to
+            // the next delimiter, then try to decode from there
+            let next = self
+                .buffer
+                .iter()
+                .skip(1)
+                .position(|byte| *byte == self.config.delimiter)
+                .map_or(self.buffer.len(), |position| position + 1);
+
+            self.buffer.advance(next);
+            skipped += next;
+
+            let _ = self.decode();
+        }
+
+        self.skipped += skipped;
+
+        skipped
     }
 }
@@ -1,3 +1,7 @@
+use std::io;
+
+use thiserror::Error;
+
 use crate::netframe::error::NetFrameError;
 
 
@@ -23,6 +27,12 @@ pub enum NetStreamErrorType {
     // |===| stream handling errors
     StreamMessageTooLong,
     StreamMessageCountZero,
+    StreamMessageCountFull,
+
+    // |===| stream configuration errors
+    // limits leave no room for a single frame, i.e. max_buffered_bytes
+    // below the biggest frame or max_queued_frames set to 0
+    StreamConfigInvalid,
 
     // |===| stream protocol errors
     // this means that frames can not be recreated from the current
@@ -35,16 +45,25 @@ pub enum NetStreamErrorType {
     // LEN bytes for data
     FramingDelimiterMismatch,
 
+    // extended length header carrying a length that fits the short one
+    FramingInvalidLength,
+
+    // frame checksum trailer does not match the frame, data got corrupted
+    // on the way or peers use different checksum modes
+    FramingChecksumMismatch,
+
     // not always an error, either we need to wait for more data or we lost
     // something and the stream has to be reset or dropped.
     // If for some reason we lost something, we won't be able to recover naturally.
     FramingTooLittleData,
 
-    // fatal error, stream can not be recovered. Option is to either drop or reset.
+    // stream is in the failure state after a protocol error. Option is to
+    // either drop, reset() or resync() the stream.
     StreamFailure,
 }
 
-#[derive(Debug, Default, PartialEq)]
+#[derive(Error, Debug, Default, PartialEq)]
+#[error("netstream error: {category:?}")]
 pub struct NetStreamErr {
     pub category: NetStreamErrorType,
 }
@@ -70,6 +89,26 @@ impl From<NetFrameError> for NetStreamErr {
                     category: NetStreamErrorType::FramingTooLittleData,
                 }
             }
+            NetFrameError::InvalidLength => {
+                Self {
+                    category: NetStreamErrorType::FramingInvalidLength,
+                }
+            }
+            NetFrameError::ChecksumMismatch => {
+                Self {
+                    category: NetStreamErrorType::FramingChecksumMismatch,
+                }
+            }
+            NetFrameError::MessageTooLong => {
+                Self {
+                    category: NetStreamErrorType::StreamMessageTooLong,
+                }
+            }
+            NetFrameError::WriteFailure(_) => {
+                Self {
+                    category: NetStreamErrorType::WriteFailure,
+                }
+            }
             _ => {
                 Self {
                     category: NetStreamErrorType::Unknown,
@@ -78,6 +117,32 @@ impl From<NetFrameError> for NetStreamErr {
         }
     }
 }
+
+// io errors surface when the stream is driven by a socket (i.e. in a codec)
+impl From<io::Error> for NetStreamErr {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted => {
+                Self {
+                    category: NetStreamErrorType::StreamClosed,
+                }
+            }
+            io::ErrorKind::WriteZero => {
+                Self {
+                    category: NetStreamErrorType::WriteFailure,
+                }
+            }
+            _ => {
+                Self {
+                    category: NetStreamErrorType::Generic,
+                }
+            }
+        }
+    }
+}
 /*
 Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
 
@@ -20,5 +20,7 @@ pub mod error;
 pub mod types;
 
 
+#[cfg(test)]
+pub mod tests_builder;
 #[cfg(test)]
 pub mod tests_netstream;
@@ -0,0 +1,180 @@
+/*
+Copyright (C) 2023 ErgLabs <dev@erglabs.org>.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use crate::{
+    netframe::types::{NetFrame, NetFrameChecksum},
+    netstream::{
+        error::{NetStreamErr, NetStreamErrorType},
+        types::{
+            FramingStream,
+            NetStream,
+            NetStreamBuilder,
+            NetStreamConfig,
+            NetStreamMode,
+            NetStreamState,
+        },
+    },
+};
+
+
+#[test]
+fn netstreambuilder_ok_defaults() {
+    assert_eq!(NetStream::builder().build().unwrap(), NetStream::new());
+    let config = NetStreamConfig {
+        max_frame_size: 100,
+        ..Default::default()
+    };
+    assert_eq!(
+        NetStreamBuilder::from(config.clone()).build().unwrap(),
+        NetStream::with_config(config)
+    );
+}
+
+#[test]
+fn netstreambuilder_ok_settings() {
+    let stream = NetStream::builder()
+        .max_buffered_bytes(4096)
+        .max_queued_frames(2)
+        .max_frame_size(1024)
+        .delimiter(0x7E)
+        .mode(NetStreamMode::Resync)
+        .checksum(NetFrameChecksum::Crc32)
+        .build()
+        .unwrap();
+    assert_eq!(
+        stream.config,
+        NetStreamConfig {
+            max_buffered_bytes: 4096,
+            max_queued_frames: 2,
+            max_frame_size: 1024,
+            checksum: NetFrameChecksum::Crc32,
+            delimiter: 0x7E,
+            mode: NetStreamMode::Resync,
+        }
+    );
+}
+
+#[test]
+fn netstreambuilder_ok_limits_per_instance() {
+    // trusted peer with generous limits next to a restricted one
+    let mut internal = NetStream::builder().max_frame_size(8192).build().unwrap();
+    let mut external = NetStream::builder()
+        .max_frame_size(16)
+        .max_queued_frames(1)
+        .build()
+        .unwrap();
+    let big = NetFrame::new(0x01, vec![0x11; 1000]).encode().unwrap();
+    assert_eq!(internal.write(&big), Ok(big.len()));
+    assert_eq!(
+        external.write(&big),
+        Err(NetStreamErr {
+            category: NetStreamErrorType::StreamMessageTooLong,
+        })
+    );
+    // second frame stays in the buffer until the first one is taken
+    let small = NetFrame::new(0x01, vec![0x22; 4]);
+    let mut buffer = small.encode().unwrap();
+    buffer.extend(small.encode().unwrap());
+    let mut external = NetStream::builder().max_queued_frames(1).build().unwrap();
+    assert_eq!(external.write(&buffer), Ok(buffer.len()));
+    assert_eq!(external.frames.len(), 1);
+    assert_eq!(external.next(), Ok(small.clone()));
+    assert_eq!(external.next(), Ok(small));
+}
+
+#[test]
+fn netstreambuilder_ok_resync_mode() {
+    let mut stream = NetStream::builder()
+        .mode(NetStreamMode::Resync)
+        .build()
+        .unwrap();
+    let frame = NetFrame::new(0x01, vec![0x11; 4]);
+    let mut buffer = vec![0xAB, 0xCD, 0xEF];
+    buffer.extend(frame.encode().unwrap());
+    // garbage is skipped instead of failing the stream
+    assert_eq!(stream.write(&buffer), Ok(buffer.len()));
+    assert_eq!(stream.state, NetStreamState::Empty);
+    assert_eq!(stream.skipped, 3);
+    assert_eq!(stream.next(), Ok(frame.clone()));
+    // the same data fails a strict one
+    let mut stream = NetStream::builder()
+        .mode(NetStreamMode::Strict)
+        .build()
+        .unwrap();
+    assert_eq!(
+        stream.write(&buffer),
+        Err(NetStreamErr {
+            category: NetStreamErrorType::FramingDelimiterMismatch,
+        })
+    );
+    assert_eq!(stream.state, NetStreamState::Failure);
+    assert_eq!(stream.skipped, 0);
+}
+
+#[test]
+fn netstreambuilder_ok_resync_mode_checksum() {
+    let mut stream = NetStream::builder()
+        .mode(NetStreamMode::Resync)
+        .checksum(NetFrameChecksum::XxHash32)
+        .build()
+        .unwrap();
+    let good = NetFrame::new(0x01, vec![0x11; 8]);
+    // no zero bytes past the delimiter, so resync can not stop inside it
+    let mut bad = NetFrame::new(0x02, vec![0x22; 0x0101])
+        .encode_with(NetFrameChecksum::XxHash32)
+        .unwrap();
+    bad[6] ^= 0x01;
+    assert!(!bad[1..].contains(&0x00));
+    let mut buffer = bad.clone();
+    buffer.extend(good.encode_with(NetFrameChecksum::XxHash32).unwrap());
+    assert_eq!(stream.write(&buffer), Ok(buffer.len()));
+    assert_eq!(stream.skipped, bad.len());
+    assert_eq!(stream.next(), Ok(good));
+    assert!(stream.next().is_err());
+}
+
+#[test]
+fn netstreambuilder_failure_invalid_limits() {
+    let invalid = Err(NetStreamErr {
+        category: NetStreamErrorType::StreamConfigInvalid,
+    });
+    // buffer has to fit the biggest frame, header included
+    assert_eq!(
+        NetStream::builder().max_buffered_bytes(1024).build(),
+        invalid
+    );
+    assert_eq!(
+        NetStream::builder()
+            .max_frame_size(1020)
+            .max_buffered_bytes(1024)
+            .build()
+            .unwrap()
+            .config
+            .max_buffered_bytes,
+        1024
+    );
+    // the checksum trailer too
+    assert_eq!(
+        NetStream::builder()
+            .max_frame_size(1020)
+            .max_buffered_bytes(1024)
+            .checksum(NetFrameChecksum::Crc32)
+            .build(),
+        invalid
+    );
+    assert_eq!(NetStream::builder().max_queued_frames(0).build(), invalid);
+    assert_eq!(NetStream::builder().max_frame_size(0).build(), invalid);
+}
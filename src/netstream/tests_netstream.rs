@@ -14,11 +14,17 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+use bytes::Bytes;
+
 use crate::{
-    netframe::types::NetFrame,
+    netframe::{
+        consts::{NETFRAME_DELIMITER, NETFRAME_MAX_DATA_SIZE},
+        types::{NetFrame, NetFrameChecksum},
+    },
     netstream::{
-        error::NetStreamErr,
-        types::{FramingStream, NetStream, NetStreamState},
+        consts::{NETSTREAM_EXTERNAL_CAPACITY, NETSTREAM_INTERNAL_CAPACITY},
+        error::{NetStreamErr, NetStreamErrorType},
+        types::{FramingStream, NetStream, NetStreamConfig, NetStreamState},
     },
 };
 
@@ -30,13 +36,13 @@ fn netstream_ok_write_frame_and_next() {
     // tag is 0x00, correct
     // size is 1 in network byte order
     let buffer: Vec<u8> = vec![0x00, 0x00, 0x00, 0x01, 0xAB];
-    let wanted_result: Result<(), NetStreamErr> = Ok(());
+    let wanted_result: Result<usize, NetStreamErr> = Ok(5);
     // write succeseded
-    assert_eq!(stream.write(buffer), wanted_result);
+    assert_eq!(stream.write(&buffer), wanted_result);
     // desired frame
     let wanted_frame: NetFrame = NetFrame {
         tag: 0x00,
-        data: vec![0xAB],
+        data: Bytes::from_static(&[0xAB]),
     };
     // next frame is ready, pop it off
     assert_eq!(stream.clone().next(), Ok(wanted_frame));
@@ -56,10 +62,10 @@ fn netstream_ok_write_in_progress() {
     // desired frame
     let wanted_frame: NetFrame = NetFrame {
         tag: 0x00,
-        data: vec![0xAB],
+        data: Bytes::from_static(&[0xAB]),
     };
     // too little data
-    assert_eq!(stream.write(buffer0), Ok(()));
+    assert_eq!(stream.write(&buffer0), Ok(2));
     assert_eq!(
         stream.next(),
         Err(NetStreamErr {
@@ -67,7 +73,7 @@ fn netstream_ok_write_in_progress() {
         })
     );
     // too little data
-    assert_eq!(stream.write(buffer1), Ok(()));
+    assert_eq!(stream.write(&buffer1), Ok(2));
     assert_eq!(
         stream.next(),
         Err(NetStreamErr {
@@ -75,9 +81,360 @@ fn netstream_ok_write_in_progress() {
         })
     );
     // enough for one frame
-    assert_eq!(stream.write(buffer2), Ok(()));
+    assert_eq!(stream.write(&buffer2), Ok(1));
     // next frame is ready, pop it off
     assert_eq!(stream.clone().next(), Ok(wanted_frame));
     // no more frames
     assert_eq!(stream.clone().state, NetStreamState::Empty);
 }
+
+#[test]
+fn netstream_ok_write_two_coalesced_frames() {
+    let mut stream = NetStream::new();
+    // two complete frames in a single chunk
+    let buffer: Vec<u8> = vec![
+        0x00, 0x01, 0x00, 0x01, 0xAB, 0x00, 0x02, 0x00, 0x02, 0xCD, 0xEF,
+    ];
+    assert_eq!(stream.write(&buffer), Ok(buffer.len()));
+    assert_eq!(
+        stream.next(),
+        Ok(NetFrame {
+            tag: 0x01,
+            data: Bytes::from_static(&[0xAB]),
+        })
+    );
+    assert_eq!(
+        stream.next(),
+        Ok(NetFrame {
+            tag: 0x02,
+            data: Bytes::from_static(&[0xCD, 0xEF]),
+        })
+    );
+    assert_eq!(
+        stream.next(),
+        Err(NetStreamErr {
+            category: NetStreamErrorType::StreamMessageCountZero,
+        })
+    );
+    assert_eq!(stream.state, NetStreamState::Empty);
+}
+
+#[test]
+fn netstream_ok_write_many_coalesced_frames() {
+    for count in 2..=64u8 {
+        let mut stream = NetStream::new();
+        let frames: Vec<NetFrame> = (0..count)
+            .map(|i| NetFrame::new(i, vec![i; i as usize]))
+            .collect();
+        let buffer: Vec<u8> = frames
+            .iter()
+            .flat_map(|frame| frame.encode().unwrap())
+            .collect();
+        assert_eq!(stream.write(&buffer), Ok(buffer.len()));
+        for frame in frames {
+            assert_eq!(stream.next(), Ok(frame));
+        }
+        assert!(stream.next().is_err());
+        assert_eq!(stream.state, NetStreamState::Empty);
+    }
+}
+
+#[test]
+fn netstream_ok_write_coalesced_frames_with_trailing_partial() {
+    let mut stream = NetStream::new();
+    // two complete frames followed by a header and half of the data
+    let buffer: Vec<u8> = vec![
+        0x00, 0x01, 0x00, 0x01, 0xAB, 0x00, 0x02, 0x00, 0x01, 0xCD, 0x00, 0x03, 0x00, 0x02, 0x11,
+    ];
+    assert_eq!(stream.write(&buffer), Ok(buffer.len()));
+    assert_eq!(stream.state, NetStreamState::InProgress);
+    assert_eq!(stream.frames.len(), 2);
+    assert_eq!(stream.buffer, vec![0x00, 0x03, 0x00, 0x02, 0x11]);
+    // rest of the partial frame and a partial header of the next one
+    assert_eq!(stream.write(&[0x22, 0x00, 0x04]), Ok(3));
+    assert_eq!(stream.frames.len(), 3);
+    assert_eq!(stream.buffer, vec![0x00, 0x04]);
+    assert_eq!(stream.next().unwrap().data, vec![0xAB]);
+    assert_eq!(stream.next().unwrap().data, vec![0xCD]);
+    assert_eq!(
+        stream.next(),
+        Ok(NetFrame {
+            tag: 0x03,
+            data: Bytes::from_static(&[0x11, 0x22]),
+        })
+    );
+    assert_eq!(stream.state, NetStreamState::InProgress);
+}
+
+#[test]
+fn netstream_ok_frame_queue_full_holds_back_frames() {
+    let mut stream = NetStream::new();
+    // one more frame than the queue can hold, plus a partial one
+    let frame = NetFrame::new(0x00, vec![0xAB]).encode().unwrap();
+    let mut buffer: Vec<u8> = frame.repeat(NETSTREAM_EXTERNAL_CAPACITY + 1);
+    buffer.extend_from_slice(&[0x00, 0x00]);
+    // everything fits in the byte buffer, decoding stops at the queue limit
+    assert_eq!(stream.write(&buffer), Ok(buffer.len()));
+    assert_eq!(stream.frames.len(), NETSTREAM_EXTERNAL_CAPACITY);
+    assert_eq!(stream.buffer.len(), frame.len() + 2);
+    // bytes are still buffered while the queue is full
+    assert_eq!(stream.write(&[0x00, 0x01]), Ok(2));
+    assert_eq!(stream.buffer.len(), frame.len() + 4);
+    // draining picks up the frame held back in the buffer
+    assert!(stream.next().is_ok());
+    assert_eq!(stream.frames.len(), NETSTREAM_EXTERNAL_CAPACITY);
+    assert_eq!(stream.buffer, vec![0x00, 0x00, 0x00, 0x01]);
+}
+
+#[test]
+fn netstream_failure_byte_buffer_full() {
+    let mut stream = NetStream::new();
+    let frame = NetFrame::new(0x00, vec![0xAB; NETFRAME_MAX_DATA_SIZE])
+        .encode()
+        .unwrap();
+    let buffer: Vec<u8> = frame.repeat(NETSTREAM_EXTERNAL_CAPACITY + 4);
+    // fill up the frame queue and then the byte buffer
+    let mut written = 0;
+    let err = loop {
+        match stream.write(&buffer[written..]) {
+            Ok(accepted) => written += accepted,
+            Err(err) => break err,
+        }
+    };
+    assert_eq!(
+        err,
+        NetStreamErr {
+            category: NetStreamErrorType::StreamMessageCountFull,
+        }
+    );
+    assert_eq!(stream.frames.len(), NETSTREAM_EXTERNAL_CAPACITY);
+    assert_eq!(stream.buffer.len(), NETSTREAM_INTERNAL_CAPACITY);
+    // refused write did not touch the stream
+    assert_eq!(
+        stream.write(&buffer[written..]),
+        Err(NetStreamErr {
+            category: NetStreamErrorType::StreamMessageCountFull,
+        })
+    );
+    assert_eq!(stream.frames.len(), NETSTREAM_EXTERNAL_CAPACITY);
+    assert_eq!(stream.buffer.len(), NETSTREAM_INTERNAL_CAPACITY);
+    // draining makes room and the rest of the input is accepted on retry
+    let mut frames = 0;
+    while written < buffer.len() {
+        while stream.next().is_ok() {
+            frames += 1;
+        }
+        written += stream.write(&buffer[written..]).unwrap();
+    }
+    while stream.next().is_ok() {
+        frames += 1;
+    }
+    assert_eq!(frames, NETSTREAM_EXTERNAL_CAPACITY + 4);
+    assert_eq!(stream.state, NetStreamState::Empty);
+}
+
+#[test]
+fn netstream_failure_delimiter_mismatch_enters_failure() {
+    let mut stream = NetStream::new();
+    // one good frame, followed by garbage
+    let buffer: Vec<u8> = vec![0x00, 0x01, 0x00, 0x01, 0xAB, 0x01, 0x02, 0x03, 0x04];
+    assert_eq!(
+        stream.write(&buffer),
+        Err(NetStreamErr {
+            category: NetStreamErrorType::FramingDelimiterMismatch,
+        })
+    );
+    assert_eq!(stream.state, NetStreamState::Failure);
+    // garbage is kept, good frame is still available
+    assert_eq!(stream.buffer, vec![0x01, 0x02, 0x03, 0x04]);
+    assert_eq!(stream.next().unwrap().data, vec![0xAB]);
+    // further writes are refused
+    assert_eq!(
+        stream.write(&[0x00, 0x01, 0x00, 0x00]),
+        Err(NetStreamErr {
+            category: NetStreamErrorType::StreamFailure,
+        })
+    );
+}
+
+#[test]
+fn netstream_ok_reset_after_failure() {
+    let mut stream = NetStream::new();
+    let buffer: Vec<u8> = vec![0x00, 0x01, 0x00, 0x01, 0xAB, 0x01, 0x02, 0x03, 0x04];
+    assert!(stream.write(&buffer).is_err());
+    stream.reset();
+    assert_eq!(stream.state, NetStreamState::Empty);
+    assert!(stream.buffer.is_empty());
+    assert!(stream.frames.is_empty());
+    // stream is usable again
+    assert_eq!(stream.write(&[0x00, 0x02, 0x00, 0x01, 0xCD]), Ok(5));
+    assert_eq!(stream.next().unwrap().data, vec![0xCD]);
+}
+
+#[test]
+fn netstream_ok_resync_skips_to_next_delimiter() {
+    let mut stream = NetStream::new();
+    // three bytes of garbage, then a good frame and a partial one
+    let buffer: Vec<u8> = vec![0x11, 0x22, 0x33, 0x00, 0x01, 0x00, 0x01, 0xAB, 0x00, 0x02];
+    assert!(stream.write(&buffer).is_err());
+    assert_eq!(stream.state, NetStreamState::Failure);
+    assert_eq!(stream.resync(), 3);
+    assert_eq!(stream.skipped, 3);
+    assert_eq!(stream.state, NetStreamState::InProgress);
+    assert_eq!(stream.next().unwrap().data, vec![0xAB]);
+    // decoding continues where it left off
+    assert_eq!(stream.write(&[0x00, 0x01, 0xCD]), Ok(3));
+    assert_eq!(
+        stream.next(),
+        Ok(NetFrame {
+            tag: 0x02,
+            data: Bytes::from_static(&[0xCD]),
+        })
+    );
+    // resync on a healthy stream is a no-op
+    assert_eq!(stream.resync(), 0);
+    assert_eq!(stream.skipped, 3);
+}
+
+#[test]
+fn netstream_ok_resync_without_delimiter_drops_buffer() {
+    let mut stream = NetStream::new();
+    let buffer: Vec<u8> = vec![0x11, 0x22, 0x33, 0x44, 0x55];
+    assert!(stream.write(&buffer).is_err());
+    assert_eq!(stream.resync(), 5);
+    assert_eq!(stream.state, NetStreamState::Empty);
+    assert!(stream.buffer.is_empty());
+}
+
+#[test]
+fn netstream_ok_frame_data_is_not_copied() {
+    let mut stream = NetStream::new();
+    let buffer: Vec<u8> = vec![
+        0x00, 0x01, 0x00, 0x01, 0xAB, 0x00, 0x02, 0x00, 0x02, 0xCD, 0xEF,
+    ];
+    assert_eq!(stream.write(&buffer), Ok(buffer.len()));
+    let first = stream.next().unwrap();
+    let second = stream.next().unwrap();
+    // both frames point into the same allocation, one header apart
+    assert_eq!(
+        second.data.as_ptr(),
+        first.data[first.data.len()..].as_ptr().wrapping_add(4)
+    );
+}
+
+#[test]
+fn netstream_ok_extended_frame_not_enabled() {
+    // 0xFFFF is a regular length unless extended frames are enabled
+    let mut stream = NetStream::new();
+    let frame = NetFrame::new(0x01, vec![0xAB; NETFRAME_MAX_DATA_SIZE]);
+    let encoded = frame.encode().unwrap();
+    assert_eq!(encoded[2..4], [0xFF, 0xFF]);
+    assert_eq!(stream.write(&encoded), Ok(encoded.len()));
+    assert_eq!(stream.next(), Ok(frame));
+    // smaller cap, rejected as soon as the header is in
+    let mut stream = NetStream::with_max_frame_size(1000);
+    assert_eq!(
+        stream.write(&encoded[..4]),
+        Err(NetStreamErr {
+            category: NetStreamErrorType::StreamMessageTooLong,
+        })
+    );
+    assert_eq!(stream.state, NetStreamState::Failure);
+}
+
+#[test]
+fn netstream_ok_extended_frames_up_to_max_frame_size() {
+    let max_frame_size = 4 * NETSTREAM_INTERNAL_CAPACITY;
+    let mut stream = NetStream::with_max_frame_size(max_frame_size);
+    // buffer grows to hold the biggest frame
+    assert!(stream.config.max_buffered_bytes >= max_frame_size + 8);
+    let frame = NetFrame::new(0x01, vec![0xAB; max_frame_size]);
+    let small = NetFrame::new(0x02, vec![0xCD]);
+    let encode = |frame: &NetFrame, max_size| {
+        frame
+            .encode_delimited(max_size, NetFrameChecksum::None, NETFRAME_DELIMITER)
+            .unwrap()
+    };
+    let mut buffer = encode(&frame, max_frame_size);
+    buffer.extend(encode(&small, max_frame_size));
+    // fed in read sized chunks
+    let mut written = 0;
+    while written < buffer.len() {
+        let end = buffer.len().min(written + 4096);
+        written += stream.write(&buffer[written..end]).unwrap();
+    }
+    assert_eq!(stream.next(), Ok(frame));
+    assert_eq!(stream.next(), Ok(small));
+    // anything bigger is still rejected
+    let too_big = NetFrame::new(0x01, vec![0xAB; max_frame_size + 1]);
+    assert_eq!(
+        stream.write(&encode(&too_big, 2 * max_frame_size)[..8]),
+        Err(NetStreamErr {
+            category: NetStreamErrorType::StreamMessageTooLong,
+        })
+    );
+}
+
+#[test]
+fn netstream_checksum_failure_and_resync() {
+    let mut stream = NetStream::with_checksum(NetFrameChecksum::Crc32);
+    let good = NetFrame::new(0x01, vec![0x11; 8]);
+    // no zero bytes past the delimiter, so resync can not stop inside it
+    let mut bad = NetFrame::new(0x02, vec![0x22; 0x0101])
+        .encode_with(NetFrameChecksum::Crc32)
+        .unwrap();
+    bad[6] ^= 0x01;
+    assert!(!bad[1..].contains(&0x00));
+    let mut buffer = good.encode_with(NetFrameChecksum::Crc32).unwrap();
+    buffer.extend(&bad);
+    buffer.extend(good.encode_with(NetFrameChecksum::Crc32).unwrap());
+    assert_eq!(
+        stream.write(&buffer),
+        Err(NetStreamErr {
+            category: NetStreamErrorType::FramingChecksumMismatch,
+        })
+    );
+    assert_eq!(stream.state, NetStreamState::Failure);
+    assert_eq!(stream.next(), Ok(good.clone()));
+    // corrupted frame is skipped, the one after it is decoded
+    assert_eq!(stream.resync(), bad.len());
+    assert_eq!(stream.next(), Ok(good));
+    assert_eq!(stream.state, NetStreamState::Empty);
+}
+
+#[test]
+fn netstream_ok_custom_delimiter() {
+    let mut stream = NetStream::with_config(NetStreamConfig {
+        delimiter: 0x7E,
+        ..Default::default()
+    });
+    let frame = NetFrame::new(0x01, vec![0x11; 4]);
+    let mut encoded = vec![0; frame.encoded_len()];
+    frame
+        .encode_into_delimited(
+            &mut encoded,
+            NETFRAME_MAX_DATA_SIZE,
+            NetFrameChecksum::None,
+            0x7E,
+        )
+        .unwrap();
+    assert_eq!(encoded[0], 0x7E);
+    // garbage in front, the default delimiter is not special any more
+    let mut buffer = vec![0x00, 0x00, 0x01];
+    buffer.extend(&encoded);
+    assert_eq!(
+        stream.write(&buffer),
+        Err(NetStreamErr {
+            category: NetStreamErrorType::FramingDelimiterMismatch,
+        })
+    );
+    assert_eq!(stream.resync(), 3);
+    assert_eq!(stream.next(), Ok(frame.clone()));
+    // frames with the default delimiter are rejected
+    assert_eq!(
+        stream.write(&frame.encode().unwrap()),
+        Err(NetStreamErr {
+            category: NetStreamErrorType::FramingDelimiterMismatch,
+        })
+    );
+}
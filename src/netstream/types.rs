@@ -16,7 +16,13 @@ limitations under the License.
 
 use std::collections::VecDeque;
 
-use crate::{netframe::types::NetFrame, netstream::error::NetStreamErr};
+use bytes::BytesMut;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    netframe::types::{NetFrame, NetFrameChecksum},
+    netstream::error::NetStreamErr,
+};
 
 
 pub type FramingStreamResult = Result<(), NetStreamErr>;
@@ -25,11 +31,23 @@ pub trait FramingStream: Send + Sync {
     // returns next frame.
     // If no frame is available returns
     fn next(&mut self) -> Result<NetFrame, NetStreamErr>;
-    // writes bulk data to stream, framing is happening on each write
+    // writes bulk data to stream, framing is happening on each write.
+    // Returns the number of bytes accepted, which can be less than
+    // data.len() when the stream is close to its capacity. The rest
+    // has to be written again after draining frames with next().
+    // Protocol errors are found while decoding the accepted data, so in
+    // that case the data (up to available()) is taken in, and the stream
+    // enters the Failure state, unless it runs in NetStreamMode::Resync.
     fn write(
         &mut self,
-        data: Vec<u8>,
-    ) -> Result<(), NetStreamErr>;
+        data: &[u8],
+    ) -> Result<usize, NetStreamErr>;
+    // discards all buffered data and queued frames
+    fn reset(&mut self);
+    // skips data up to the next frame delimiter and keeps decoding from
+    // there, until the stream is out of the failure state.
+    // Returns the number of bytes skipped.
+    fn resync(&mut self) -> usize;
 }
 
 #[derive(Debug, Clone, Default, PartialEq)]
@@ -40,10 +58,60 @@ pub enum NetStreamState {
     Failure,
 }
 
+// per stream limits, defaults are the NETSTREAM_* constants.
+// Also the [stream] section of the configuration file, see netconfig.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetStreamConfig {
+    // bytes held in the buffer, including frames not decoded yet
+    pub max_buffered_bytes: usize,
+    // decoded frames waiting for next()
+    pub max_queued_frames: usize,
+    // largest frame data accepted, raise above NETFRAME_MAX_DATA_SIZE
+    // to accept extended length frames
+    pub max_frame_size: usize,
+    // trailer expected on every frame, has to match the peer
+    pub checksum: NetFrameChecksum,
+    // first byte of every frame, has to match the peer
+    pub delimiter: u8,
+    // what write() does about protocol errors
+    pub mode: NetStreamMode,
+}
+
+// how the stream reacts to data it can not decode
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetStreamMode {
+    // the error is returned and the stream stays in the Failure state
+    // until reset() or resync() is called
+    #[default]
+    Strict,
+
+    // garbage is skipped up to the next delimiter and decoding goes on,
+    // write() only reports capacity errors. Skipped bytes are counted
+    // in NetStream::skipped.
+    Resync,
+}
+
+// per instance limits, NetStream::builder() starts with the defaults
+//
+//   let stream = NetStream::builder()
+//       .max_frame_size(1024)
+//       .checksum(NetFrameChecksum::Crc32)
+//       .mode(NetStreamMode::Resync)
+//       .build()?;
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NetStreamBuilder {
+    pub config: NetStreamConfig,
+}
+
 // todo:esavier visibility?
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct NetStream {
+    pub config: NetStreamConfig,
     pub frames: VecDeque<NetFrame>,
-    pub buffer: Vec<u8>,
+    pub buffer: BytesMut,
     pub state: NetStreamState,
+    // total number of bytes dropped by resync()
+    pub skipped: usize,
 }